@@ -1,20 +1,35 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path, str::FromStr as _};
 
 use async_walkdir::{Filtering, WalkDir};
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    config::NetworkConfig,
     dir::{BUCKETS_DIR, INSTALL_DIR},
-    error::Result,
+    error::{Error, Result},
     utils::get_stem,
+    Context as _,
 };
 
-use super::bucket_app::BucketApp;
+use credentials::CredentialChain;
+pub use credentials::TOKEN_VAR;
+
+mod credentials;
+#[cfg(test)]
+mod test;
+
+use super::{
+    bucket_app::BucketApp,
+    installed_app::{installed_apps, InstalledApp},
+    manifest::Manifest,
+};
 
 pub async fn get_buckets() -> Result<Vec<Bucket>> {
     let mut buckets = Vec::new();
-    let mut reader = tokio::fs::read_dir(INSTALL_DIR.clone().join("buckets")).await?;
+    let mut reader = tokio::fs::read_dir(INSTALL_DIR.clone().join("buckets"))
+        .await
+        .context("Failed to read buckets directory")?;
     while let Ok(Some(entry)) = reader.next_entry().await {
         let bucket = Bucket::from_name(entry.file_name().to_str().unwrap());
         buckets.push(bucket);
@@ -22,6 +37,288 @@ pub async fn get_buckets() -> Result<Vec<Bucket>> {
     Ok(buckets)
 }
 
+/// Buckets that can be added by name only. Same as `buckets.json` of Scoop.
+pub const KNOWN_BUCKETS: &[(&str, &str)] = &[
+    ("main", "https://github.com/ScoopInstaller/Main"),
+    ("extras", "https://github.com/ScoopInstaller/Extras"),
+    ("versions", "https://github.com/ScoopInstaller/Versions"),
+    ("nirsoft", "https://github.com/ScoopInstaller/Nirsoft"),
+    (
+        "sysinternals",
+        "https://github.com/niheaven/scoop-sysinternals",
+    ),
+    ("php", "https://github.com/ScoopInstaller/PHP"),
+    (
+        "nerd-fonts",
+        "https://github.com/matthewjberger/scoop-nerd-fonts",
+    ),
+    (
+        "nonportable",
+        "https://github.com/ScoopInstaller/Nonportable",
+    ),
+    ("java", "https://github.com/ScoopInstaller/Java"),
+    ("games", "https://github.com/Calinou/scoop-games"),
+];
+
+/// Get the repository url of a known bucket
+pub fn known_bucket_url(name: &str) -> Option<&'static str> {
+    KNOWN_BUCKETS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, url)| *url)
+}
+
+/// Check that `name` is the name of a directory right in the buckets directory, so that it
+/// can't point elsewhere, like `../foo` or `C:\foo`
+pub fn validate_bucket_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', ':']) {
+        return Err(Error::InvalidBucketName(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Result of [`Bucket::check_manifests`]
+#[derive(Debug, Default)]
+pub struct ManifestCheck {
+    /// Number of manifests that could be parsed
+    pub parsed: usize,
+    /// Names of apps whose manifest could not be parsed
+    pub failed: Vec<String>,
+}
+
+/// Result of [`Bucket::update`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketUpdate {
+    UpToDate,
+    /// The checked-out branch was fast-forwarded from `old` to `new`
+    Updated {
+        old: git2::Oid,
+        new: git2::Oid,
+    },
+}
+
+/// Manifest that was added, removed or version-bumped between two commits of a bucket
+#[derive(Debug, Clone)]
+pub struct ManifestChange {
+    pub name: String,
+    pub kind: ManifestChangeKind,
+    /// True if the app is currently installed from this bucket
+    pub installed: bool,
+}
+
+/// Versions are `None` if the manifest could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestChangeKind {
+    Added {
+        version: Option<String>,
+    },
+    Removed {
+        version: Option<String>,
+    },
+    VersionChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+/// Compare manifests (`*.json` files) between two commits of a bucket repository.
+/// Changes that don't touch the version are not reported.
+pub fn manifest_changes(
+    repo: &git2::Repository,
+    old: git2::Oid,
+    new: git2::Oid,
+) -> Result<Vec<(String, ManifestChangeKind)>> {
+    fn version(repo: &git2::Repository, blob: git2::Oid) -> Option<String> {
+        let blob = repo.find_blob(blob).ok()?;
+        let content = std::str::from_utf8(blob.content()).ok()?;
+        Manifest::from_str(content).ok().map(|m| m.version)
+    }
+
+    let old_tree = repo.find_commit(old)?.tree()?;
+    let new_tree = repo.find_commit(new)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+
+    let mut changes = Vec::new();
+    for delta in diff.deltas() {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+        let Some(name) = manifest_name(path) else {
+            continue;
+        };
+
+        let kind = match delta.status() {
+            git2::Delta::Added => ManifestChangeKind::Added {
+                version: version(repo, delta.new_file().id()),
+            },
+            git2::Delta::Deleted => ManifestChangeKind::Removed {
+                version: version(repo, delta.old_file().id()),
+            },
+            git2::Delta::Modified => {
+                let old = version(repo, delta.old_file().id());
+                let new = version(repo, delta.new_file().id());
+                if old == new {
+                    continue;
+                }
+                ManifestChangeKind::VersionChanged { old, new }
+            }
+            _ => continue,
+        };
+        changes.push((name, kind));
+    }
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(changes)
+}
+
+/// Get app name from the path of a manifest in the bucket repository.
+/// Same rule as [`Bucket::apps`]: `*.json` files outside of hidden directories.
+fn manifest_name(path: &Path) -> Option<String> {
+    if path
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    {
+        return None;
+    }
+    let file_name = path.file_name()?.to_string_lossy();
+    match get_stem(&file_name) {
+        (name, Some("json")) => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+/// Options to fetch from `url` with `network` applied the same way as to downloads, and
+/// `credentials` for private buckets
+fn fetch_options<'a>(
+    url: &str,
+    network: &NetworkConfig,
+    mut credentials: CredentialChain,
+    progress: impl FnMut(git2::Progress<'_>) -> bool + 'a,
+) -> Result<git2::FetchOptions<'a>> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.transfer_progress(progress);
+    callbacks.credentials(move |url, username, allowed| credentials.next(url, username, allowed));
+    if network.insecure {
+        callbacks.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+    }
+
+    let mut proxy_options = git2::ProxyOptions::new();
+    if network.proxy.is_none() {
+        // Same as downloads, which use the proxy from the environment
+        proxy_options.auto();
+    } else if let Some(proxy) = url_host(url).and_then(|host| network.proxy_for(host)) {
+        proxy_options.url(proxy);
+    }
+
+    #[cfg(not(windows))]
+    if let Some(ca_bundle) = &network.ca_bundle {
+        set_ca_bundle(ca_bundle)?;
+    }
+    // On Windows, libgit2 uses WinHTTP, which only trusts the certificates of the system. Without
+    // the CA of the bundle, the certificate would be rejected, or accepted for the wrong reason.
+    #[cfg(windows)]
+    if network.ca_bundle.is_some() && !network.insecure && url.starts_with("https://") {
+        return Err(Error::UnsupportedCaBundle);
+    }
+
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options
+        .remote_callbacks(callbacks)
+        .proxy_options(proxy_options);
+    Ok(fetch_options)
+}
+
+#[cfg(not(windows))]
+fn set_ca_bundle(path: &Path) -> Result<()> {
+    static CA_BUNDLE: std::sync::Mutex<Option<std::path::PathBuf>> = std::sync::Mutex::new(None);
+    let mut ca_bundle = CA_BUNDLE.lock().unwrap_or_else(|e| e.into_inner());
+    if ca_bundle.as_deref() != Some(path) {
+        // SAFETY: This is the only place libgit2 options are set, and the lock keeps them from
+        // being set concurrently
+        unsafe { git2::opts::set_ssl_cert_file(path)? };
+        *ca_bundle = Some(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Host of a git url, e.g. `github.com` for both `https://github.com/a/b` and `git@github.com:a/b`
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next()?;
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Body of [`Bucket::update`] for the repository of the bucket `name`
+fn update_repository(
+    repo: &git2::Repository,
+    name: &str,
+    network: &NetworkConfig,
+    progress: impl FnMut(git2::Progress<'_>) -> bool,
+) -> Result<BucketUpdate> {
+    let head = repo.head()?;
+    let (Some(head_name), Some(branch)) = (head.name(), head.shorthand()) else {
+        return Err(Error::InvalidState(format!(
+            "HEAD of bucket `{}` is not a valid branch",
+            name
+        )));
+    };
+    if !head.is_branch() {
+        return Err(Error::InvalidState(format!(
+            "HEAD of bucket `{}` is detached",
+            name
+        )));
+    }
+    let old = head.peel_to_commit()?.id();
+
+    let mut remote = repo.find_remote("origin")?;
+    let url = remote.url().unwrap_or_default().to_string();
+    let mut fetch_options =
+        fetch_options(&url, network, CredentialChain::from_env(network), progress)?;
+    remote.fetch(&[branch], Some(&mut fetch_options), None)?;
+
+    let fetch_head = repo.find_reference("FETCH_HEAD")?;
+    let fetch_commit = repo.reference_to_annotated_commit(&fetch_head)?;
+    let (analysis, _) = repo.merge_analysis(&[&fetch_commit])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(BucketUpdate::UpToDate);
+    }
+    if !analysis.is_fast_forward() {
+        return Err(Error::BucketDiverged(name.to_string()));
+    }
+
+    let mut status_options = git2::StatusOptions::new();
+    status_options
+        .include_untracked(false)
+        .include_ignored(false);
+    if !repo.statuses(Some(&mut status_options))?.is_empty() {
+        return Err(Error::BucketDirty(name.to_string()));
+    }
+
+    // Files are checked out before the branch is moved, so a conflict leaves both unchanged.
+    // The safe strategy refuses to overwrite untracked files, e.g. a local manifest that was
+    // added upstream since.
+    let new = fetch_commit.id();
+    let checkout = repo.checkout_tree(
+        repo.find_commit(new)?.as_object(),
+        Some(&mut git2::build::CheckoutBuilder::new()),
+    );
+    match checkout {
+        Err(e) if e.code() == git2::ErrorCode::Conflict => {
+            return Err(Error::BucketDirty(name.to_string()));
+        }
+        res => res?,
+    }
+    repo.find_reference(head_name)?
+        .set_target(new, &format!("scoop-rs: fast-forward to {}", new))?;
+
+    Ok(BucketUpdate::Updated { old, new })
+}
+
 #[derive(Hash, PartialEq, Eq, Serialize, Deserialize, Clone, Debug)]
 #[serde(transparent)]
 pub struct Bucket {
@@ -36,9 +333,28 @@ impl Bucket {
         }
     }
 
+    /// Clone the bucket repository into the buckets directory.
+    /// `progress` is called with the transfer progress of the clone. Returning `false` from it
+    /// cancels the clone.
+    ///
     /// NOTE: This is a blocking function
-    pub fn new_with_clone(name: &str, url: &str) -> Result<Self> {
-        let _repo = git2::Repository::clone(url, BUCKETS_DIR.join(name))?;
+    pub fn new_with_clone(
+        name: &str,
+        url: &str,
+        network: &NetworkConfig,
+        progress: impl FnMut(git2::Progress<'_>) -> bool,
+    ) -> Result<Self> {
+        validate_bucket_name(name)?;
+        let path = BUCKETS_DIR.join(name);
+        if path.exists() {
+            return Err(Error::BucketAlreadyExists(name.to_string()));
+        }
+
+        let fetch_options =
+            fetch_options(url, network, CredentialChain::from_env(network), progress)?;
+        let _repo = git2::build::RepoBuilder::new()
+            .fetch_options(fetch_options)
+            .clone(url, &path)?;
         Ok(Bucket {
             name: name.to_string(),
         })
@@ -53,8 +369,21 @@ impl Bucket {
         Ok(git2::Repository::open(self.path())?)
     }
 
+    /// Fetch `origin` and fast-forward the checked-out branch.
+    /// Diverged branches and dirty work trees are never touched and result in an error.
+    /// `network` and `progress` are same as [`Bucket::new_with_clone`].
+    ///
+    /// NOTE: This is a blocking function
+    pub fn update(
+        &self,
+        network: &NetworkConfig,
+        progress: impl FnMut(git2::Progress<'_>) -> bool,
+    ) -> Result<BucketUpdate> {
+        update_repository(&self.repository()?, &self.name, network, progress)
+    }
+
     /// Get list of apps in the bucket
-    pub async fn apps(&self) -> Result<HashSet<BucketApp>> {
+    pub async fn apps(&self) -> Result<HashSet<BucketApp<'_>>> {
         let mut apps = HashSet::new();
 
         let mut entries = WalkDir::new(self.path()).filter(|entry| async move {
@@ -94,4 +423,63 @@ impl Bucket {
 
         Ok(apps)
     }
+
+    /// Get manifests changed between two commits of this bucket, e.g. the ones returned by
+    /// [`Bucket::update`].
+    pub async fn changes(&self, old: git2::Oid, new: git2::Oid) -> Result<Vec<ManifestChange>> {
+        let changes = manifest_changes(&self.repository()?, old, new)?;
+        let installed = self
+            .installed_apps()
+            .await?
+            .into_iter()
+            .map(|app| app.name)
+            .collect::<HashSet<_>>();
+
+        Ok(changes
+            .into_iter()
+            .map(|(name, kind)| ManifestChange {
+                installed: installed.contains(&name),
+                name,
+                kind,
+            })
+            .collect())
+    }
+
+    /// Get installed apps whose current version was installed from this bucket
+    pub async fn installed_apps(&self) -> Result<Vec<InstalledApp>> {
+        let mut apps = Vec::new();
+        for app in installed_apps().await? {
+            let from_this_bucket = match app.current_version().await {
+                Ok(version) => version
+                    .install_info()
+                    .await
+                    .is_ok_and(|info| info.bucket == *self),
+                Err(_) => false,
+            };
+            if from_this_bucket {
+                apps.push(app);
+            }
+        }
+        Ok(apps)
+    }
+
+    /// Delete the bucket directory
+    pub async fn remove(self) -> Result<()> {
+        tokio::fs::remove_dir_all(self.path())
+            .await
+            .with_context(|| format!("Failed to remove bucket `{}`", self.name))
+    }
+
+    /// Try to parse all manifests in the bucket
+    pub async fn check_manifests(&self) -> Result<ManifestCheck> {
+        let mut check = ManifestCheck::default();
+        for app in self.apps().await? {
+            if app.manifest().await.is_ok() {
+                check.parsed += 1;
+            } else {
+                check.failed.push(app.name);
+            }
+        }
+        Ok(check)
+    }
 }
@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+use git2::{Cred, CredentialType};
+
+use crate::config::NetworkConfig;
+
+/// Environment variable with a token for private buckets over HTTPS, on the hosts in
+/// [`NetworkConfig::git_token_hosts`]
+pub const TOKEN_VAR: &str = "SCOOP_RS_GIT_TOKEN";
+
+/// User name sent with the token if the url has none. Hosts ignore it for tokens.
+const TOKEN_USER: &str = "x-access-token";
+
+/// User name for SSH if the url has none, as in `git@github.com:owner/bucket`
+const SSH_USER: &str = "git";
+
+/// Key files in `~/.ssh` tried after ssh-agent, same as the defaults of OpenSSH
+const SSH_KEY_FILES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Method {
+    SshAgent,
+    SshKey(PathBuf),
+    CredentialHelper,
+    Token,
+}
+
+/// Credentials for private buckets, tried in order each time the remote rejects the previous one:
+/// ssh-agent and key files for SSH, git credential helpers and [`TOKEN_VAR`] for HTTPS.
+#[derive(Default)]
+pub(crate) struct CredentialChain {
+    ssh_agent: bool,
+    ssh_keys: Vec<PathBuf>,
+    git_config: Option<git2::Config>,
+    token: Option<String>,
+    token_hosts: Vec<String>,
+    tried: Vec<Method>,
+}
+
+impl CredentialChain {
+    /// Credentials of the user: ssh-agent, keys in `~/.ssh`, the credential helpers in the git
+    /// config, and [`TOKEN_VAR`] for the hosts in `network`
+    pub(crate) fn from_env(network: &NetworkConfig) -> Self {
+        let ssh_keys = dirs::home_dir()
+            .map(|home| {
+                SSH_KEY_FILES
+                    .iter()
+                    .map(|name| home.join(".ssh").join(name))
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            ssh_agent: true,
+            ssh_keys,
+            git_config: git2::Config::open_default().ok(),
+            token: std::env::var(TOKEN_VAR).ok().filter(|t| !t.is_empty()),
+            token_hosts: network.git_token_hosts.clone(),
+            tried: Vec::new(),
+        }
+    }
+
+    /// Whether the token may be sent to `url`: over HTTPS to one of the configured hosts
+    fn token_allowed(&self, url: &str) -> bool {
+        url.starts_with("https://")
+            && super::url_host(url).is_some_and(|host| {
+                self.token_hosts
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(host))
+            })
+    }
+
+    fn methods(&self, url: &str) -> Vec<Method> {
+        let mut methods = Vec::new();
+        if self.ssh_agent {
+            methods.push(Method::SshAgent);
+        }
+        methods.extend(self.ssh_keys.iter().cloned().map(Method::SshKey));
+        if self.git_config.is_some() {
+            methods.push(Method::CredentialHelper);
+        }
+        if self.token.is_some() && self.token_allowed(url) {
+            methods.push(Method::Token);
+        }
+        methods
+    }
+
+    /// Credential to try next for `url`. This is the `credentials` callback of git2, which is
+    /// called again if the credential is rejected, so each method is tried only once.
+    pub(crate) fn next(
+        &mut self,
+        url: &str,
+        username: Option<&str>,
+        allowed: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or(SSH_USER));
+        }
+
+        for method in self.methods(url) {
+            let required = match method {
+                Method::SshAgent | Method::SshKey(_) => CredentialType::SSH_KEY,
+                Method::CredentialHelper | Method::Token => CredentialType::USER_PASS_PLAINTEXT,
+            };
+            if !allowed.contains(required) || self.tried.contains(&method) {
+                continue;
+            }
+            self.tried.push(method.clone());
+
+            let cred = match &method {
+                Method::SshAgent => Cred::ssh_key_from_agent(username.unwrap_or(SSH_USER)),
+                Method::SshKey(path) => {
+                    Cred::ssh_key(username.unwrap_or(SSH_USER), None, path, None)
+                }
+                Method::CredentialHelper => {
+                    let config = self.git_config.as_ref().expect("checked in methods");
+                    Cred::credential_helper(config, url, username)
+                }
+                Method::Token => {
+                    let token = self.token.as_deref().expect("checked in methods");
+                    Cred::userpass_plaintext(username.unwrap_or(TOKEN_USER), token)
+                }
+            };
+            // e.g. no credential helper is configured for the url
+            if let Ok(cred) = cred {
+                return Ok(cred);
+            }
+        }
+
+        Err(git2::Error::from_str(&format!(
+            "No credentials were accepted for {}. Add your key to ssh-agent, configure a git \
+             credential helper, or set {} and add the host to `git_token_hosts`",
+            url, TOKEN_VAR
+        )))
+    }
+}
+
+#[cfg(test)]
+impl CredentialChain {
+    pub(crate) fn with_git_config(mut self, config: git2::Config) -> Self {
+        self.git_config = Some(config);
+        self
+    }
+
+    pub(crate) fn with_token(mut self, token: &str, hosts: &[&str]) -> Self {
+        self.token = Some(token.to_string());
+        self.token_hosts = hosts.iter().map(|h| h.to_string()).collect();
+        self
+    }
+}
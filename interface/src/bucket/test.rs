@@ -0,0 +1,461 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use http_body_util::{BodyExt as _, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    header, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use tokio::{io::AsyncWriteExt as _, net::TcpListener};
+
+use git2::CredentialType;
+
+use super::{
+    fetch_options, manifest_changes, update_repository, validate_bucket_name, BucketUpdate,
+    CredentialChain, ManifestChangeKind,
+};
+use crate::{config::NetworkConfig, error::Error};
+
+/// `scoop:secret` in base64
+const AUTHORIZATION: &str = "Basic c2Nvb3A6c2VjcmV0";
+
+/// Serve the repositories in `root` over smart HTTP with `git http-backend`, only to requests
+/// authorized as `scoop:secret`. Returns the base url.
+async fn serve_git(root: &Path) -> String {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let root = root.to_path_buf();
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let root = root.clone();
+            let service = hyper::service::service_fn(move |req: Request<Incoming>| {
+                let root = root.clone();
+                async move { Ok::<_, Infallible>(http_backend(req, &root).await) }
+            });
+            tokio::spawn(
+                hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service),
+            );
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+async fn http_backend(req: Request<Incoming>, root: &Path) -> Response<Full<Bytes>> {
+    if req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .map(|v| v.as_bytes())
+        != Some(AUTHORIZATION.as_bytes())
+    {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(header::WWW_AUTHENTICATE, "Basic realm=\"bucket\"")
+            .body(Full::default())
+            .unwrap();
+    }
+
+    let header = |name| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let mut child = tokio::process::Command::new("git")
+        .arg("http-backend")
+        .env("GIT_PROJECT_ROOT", root)
+        .env("GIT_HTTP_EXPORT_ALL", "1")
+        .env("REQUEST_METHOD", req.method().as_str())
+        .env("PATH_INFO", req.uri().path())
+        .env("QUERY_STRING", req.uri().query().unwrap_or_default())
+        .env("CONTENT_TYPE", header(header::CONTENT_TYPE))
+        .env("HTTP_CONTENT_ENCODING", header(header::CONTENT_ENCODING))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let body = req.into_body().collect().await.unwrap().to_bytes();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(&body).await.unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().await.unwrap();
+
+    // CGI response: headers, an empty line and the body
+    let split = output
+        .stdout
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .unwrap();
+    let mut resp = Response::builder();
+    for line in String::from_utf8_lossy(&output.stdout[..split]).lines() {
+        let (name, value) = line.split_once(": ").unwrap();
+        resp = if name == "Status" {
+            resp.status(value[..3].parse::<u16>().unwrap())
+        } else {
+            resp.header(name, value)
+        };
+    }
+    resp.body(Full::new(Bytes::copy_from_slice(
+        &output.stdout[split + 4..],
+    )))
+    .unwrap()
+}
+
+/// Commit `content` as `bucket/app.json` to the repository at `path`, creating it if needed
+fn commit(path: &Path, content: &str) -> git2::Oid {
+    commit_files(path, &[("bucket/app.json", Some(content))])
+}
+
+/// Commit `files` to the repository at `path`, creating it if needed.
+/// Files with `None` as content are removed.
+fn commit_files(path: &Path, files: &[(&str, Option<&str>)]) -> git2::Oid {
+    let repo = git2::Repository::init(path).unwrap();
+    let mut index = repo.index().unwrap();
+    for (name, content) in files {
+        let file = path.join(name);
+        match content {
+            Some(content) => {
+                std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+                std::fs::write(&file, content).unwrap();
+                index.add_path(Path::new(name)).unwrap();
+            }
+            None => {
+                std::fs::remove_file(&file).unwrap();
+                index.remove_path(Path::new(name)).unwrap();
+            }
+        }
+    }
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("scoop", "scoop@example.com").unwrap();
+    let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Update app",
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+fn manifest(version: &str, description: &str) -> String {
+    format!(
+        r#"{{"version": "{}", "description": "{}", "homepage": "https://example.com", "license": "MIT"}}"#,
+        version, description
+    )
+}
+
+/// Clone `url` to `dest` with `credentials`, same as [`super::Bucket::new_with_clone`].
+/// The chain is created in the blocking thread, because git configs can't be sent to it.
+async fn clone(
+    url: &str,
+    dest: &Path,
+    credentials: impl FnOnce() -> CredentialChain + Send + 'static,
+) -> Result<(), git2::Error> {
+    let url = url.to_string();
+    let dest = dest.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let options =
+            fetch_options(&url, &NetworkConfig::default(), credentials(), |_| true).unwrap();
+        git2::build::RepoBuilder::new()
+            .fetch_options(options)
+            .clone(&url, &dest)
+            .map(|_| ())
+    })
+    .await
+    .unwrap()
+}
+
+/// Fetch `origin` of the repository at `path`, same as [`super::Bucket::update`]
+async fn fetch(
+    path: &Path,
+    credentials: impl FnOnce() -> CredentialChain + Send + 'static,
+) -> git2::Oid {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let repo = git2::Repository::open(path).unwrap();
+        let head = repo.head().unwrap();
+        let mut remote = repo.find_remote("origin").unwrap();
+        let url = remote.url().unwrap().to_string();
+        let mut options =
+            fetch_options(&url, &NetworkConfig::default(), credentials(), |_| true).unwrap();
+        remote
+            .fetch(&[head.shorthand().unwrap()], Some(&mut options), None)
+            .unwrap();
+        let fetch_head = repo.find_reference("FETCH_HEAD").unwrap();
+        let id = fetch_head.peel_to_commit().unwrap().id();
+        id
+    })
+    .await
+    .unwrap()
+}
+
+fn setup() -> (tempfile::TempDir, PathBuf) {
+    let tmp = tempfile::tempdir().unwrap();
+    let origin = tmp.path().join("origin");
+    std::fs::create_dir(&origin).unwrap();
+    commit(&origin.join("bucket"), "{}");
+    (tmp, origin)
+}
+
+/// Git config with a credential helper answering `scoop:<password>`
+fn credential_helper(tmp: &Path, password: &str) -> PathBuf {
+    let config_path = tmp.join("gitconfig");
+    std::fs::write(
+        &config_path,
+        format!(
+            "[credential]\n\thelper = \"!f() {{ echo username=scoop; echo password={}; }}; f\"\n",
+            password
+        ),
+    )
+    .unwrap();
+    config_path
+}
+
+#[tokio::test]
+async fn clone_and_fetch_private_bucket_with_credential_helper() {
+    let (tmp, origin) = setup();
+    let url = serve_git(&origin).await + "/bucket";
+    let dest = tmp.path().join("clone");
+    let config_path = credential_helper(tmp.path(), "secret");
+
+    let path = config_path.clone();
+    clone(&url, &dest, move || {
+        CredentialChain::default().with_git_config(git2::Config::open(&path).unwrap())
+    })
+    .await
+    .unwrap();
+    assert!(dest.join("bucket/app.json").exists());
+
+    let new = commit(&origin.join("bucket"), r#"{"version": "2.0"}"#);
+    let fetched = fetch(&dest, move || {
+        CredentialChain::default().with_git_config(git2::Config::open(&config_path).unwrap())
+    })
+    .await;
+    assert_eq!(fetched, new);
+}
+
+#[tokio::test]
+async fn token_is_not_sent_over_http() {
+    let (tmp, origin) = setup();
+    let url = serve_git(&origin).await + "/bucket";
+
+    let err = clone(&url, &tmp.path().join("clone"), || {
+        CredentialChain::default().with_token("secret", &["127.0.0.1"])
+    })
+    .await
+    .unwrap_err();
+    assert!(
+        err.message().contains("No credentials were accepted"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn token_is_only_sent_to_configured_hosts() {
+    let sent = |url| {
+        CredentialChain::default()
+            .with_token("secret", &["GitHub.com"])
+            .next(url, None, CredentialType::USER_PASS_PLAINTEXT)
+            .is_ok()
+    };
+    assert!(sent("https://github.com/owner/bucket"));
+    assert!(sent("https://user@github.com/owner/bucket"));
+    assert!(!sent("http://github.com/owner/bucket"));
+    assert!(!sent("https://example.com/owner/bucket"));
+    assert!(!sent("https://github.com.example.com/owner/bucket"));
+}
+
+#[tokio::test]
+async fn rejected_credentials_are_not_retried() {
+    let (tmp, origin) = setup();
+    let url = serve_git(&origin).await + "/bucket";
+    let config_path = credential_helper(tmp.path(), "wrong");
+
+    let err = clone(&url, &tmp.path().join("clone"), move || {
+        CredentialChain::default().with_git_config(git2::Config::open(&config_path).unwrap())
+    })
+    .await
+    .unwrap_err();
+    assert!(
+        err.message().contains("No credentials were accepted"),
+        "{}",
+        err
+    );
+}
+
+/// Clone of the bucket `origin` of [`setup`], to be updated from it
+fn clone_local(tmp: &Path, origin: &Path) -> git2::Repository {
+    git2::Repository::clone(origin.join("bucket").to_str().unwrap(), tmp.join("clone")).unwrap()
+}
+
+fn update(repo: &git2::Repository) -> crate::error::Result<BucketUpdate> {
+    update_repository(repo, "bucket", &NetworkConfig::default(), |_| true)
+}
+
+fn head(repo: &git2::Repository) -> git2::Oid {
+    repo.head().unwrap().peel_to_commit().unwrap().id()
+}
+
+fn read_app(repo: &git2::Repository) -> String {
+    std::fs::read_to_string(repo.workdir().unwrap().join("bucket/app.json")).unwrap()
+}
+
+#[test]
+fn update_fast_forwards() {
+    let (tmp, origin) = setup();
+    let repo = clone_local(tmp.path(), &origin);
+    let old = head(&repo);
+    let new = commit(&origin.join("bucket"), r#"{"version": "2.0"}"#);
+
+    assert_eq!(update(&repo).unwrap(), BucketUpdate::Updated { old, new });
+    assert_eq!(head(&repo), new);
+    assert_eq!(read_app(&repo), r#"{"version": "2.0"}"#);
+}
+
+#[test]
+fn update_already_up_to_date() {
+    let (tmp, origin) = setup();
+    let repo = clone_local(tmp.path(), &origin);
+    let old = head(&repo);
+
+    assert_eq!(update(&repo).unwrap(), BucketUpdate::UpToDate);
+    assert_eq!(head(&repo), old);
+}
+
+#[test]
+fn update_refuses_diverged_branch() {
+    let (tmp, origin) = setup();
+    let repo = clone_local(tmp.path(), &origin);
+    let local = commit(repo.workdir().unwrap(), r#"{"version": "local"}"#);
+    commit(&origin.join("bucket"), r#"{"version": "2.0"}"#);
+
+    assert!(matches!(update(&repo), Err(Error::BucketDiverged(_))));
+    assert_eq!(head(&repo), local);
+    assert_eq!(read_app(&repo), r#"{"version": "local"}"#);
+}
+
+#[test]
+fn update_refuses_dirty_worktree() {
+    let (tmp, origin) = setup();
+    let repo = clone_local(tmp.path(), &origin);
+    let old = head(&repo);
+    commit(&origin.join("bucket"), r#"{"version": "2.0"}"#);
+    std::fs::write(
+        repo.workdir().unwrap().join("bucket/app.json"),
+        r#"{"version": "edited"}"#,
+    )
+    .unwrap();
+
+    assert!(matches!(update(&repo), Err(Error::BucketDirty(_))));
+    assert_eq!(head(&repo), old);
+    assert_eq!(read_app(&repo), r#"{"version": "edited"}"#);
+}
+
+#[test]
+fn report_manifest_changes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path();
+    let v1 = manifest("1.0", "App");
+    let old = commit_files(
+        path,
+        &[
+            ("bucket/bumped.json", Some(&v1)),
+            ("bucket/edited.json", Some(&v1)),
+            ("bucket/removed.json", Some(&v1)),
+        ],
+    );
+    let new = commit_files(
+        path,
+        &[
+            ("bucket/bumped.json", Some(&manifest("2.0", "App"))),
+            ("bucket/edited.json", Some(&manifest("1.0", "Edited"))),
+            ("bucket/removed.json", None),
+            ("bucket/added.json", Some(&manifest("3.0", "App"))),
+            ("bucket/broken.json", Some("{")),
+            (".vscode/settings.json", Some("{}")),
+        ],
+    );
+
+    let repo = git2::Repository::open(path).unwrap();
+    let version = |v: &str| Some(v.to_string());
+    assert_eq!(
+        manifest_changes(&repo, old, new).unwrap(),
+        vec![
+            (
+                "added".to_string(),
+                ManifestChangeKind::Added {
+                    version: version("3.0")
+                }
+            ),
+            (
+                "broken".to_string(),
+                ManifestChangeKind::Added { version: None }
+            ),
+            (
+                "bumped".to_string(),
+                ManifestChangeKind::VersionChanged {
+                    old: version("1.0"),
+                    new: version("2.0")
+                }
+            ),
+            (
+                "removed".to_string(),
+                ManifestChangeKind::Removed {
+                    version: version("1.0")
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn update_refuses_to_overwrite_untracked_files() {
+    let (tmp, origin) = setup();
+    let repo = clone_local(tmp.path(), &origin);
+    let old = head(&repo);
+    let local = repo.workdir().unwrap().join("bucket/local.json");
+    std::fs::write(&local, r#"{"version": "local"}"#).unwrap();
+    commit_files(
+        &origin.join("bucket"),
+        &[("bucket/local.json", Some(r#"{"version": "2.0"}"#))],
+    );
+
+    assert!(matches!(update(&repo), Err(Error::BucketDirty(_))));
+    assert_eq!(head(&repo), old);
+    assert_eq!(
+        std::fs::read_to_string(&local).unwrap(),
+        r#"{"version": "local"}"#
+    );
+}
+
+#[test]
+fn bucket_names_stay_in_buckets_dir() {
+    for name in ["main", "my-bucket", "bucket.old"] {
+        assert!(validate_bucket_name(name).is_ok(), "{}", name);
+    }
+    for name in [
+        "", ".", "..", "../foo", "foo/bar", "..\\foo", "C:\\foo", "C:",
+    ] {
+        assert!(
+            matches!(validate_bucket_name(name), Err(Error::InvalidBucketName(_))),
+            "{}",
+            name
+        );
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 
-use crate::{dir::INSTALL_DIR, error::Result};
+use crate::error::Result;
 
 use super::{bucket::Bucket, installed_app::InstalledApp, manifest::Manifest};
 
@@ -24,10 +24,9 @@ impl BucketApp<'_> {
     /// Check if the app is installed
     /// If the app is installed, return the InstalledApp
     pub async fn installed(&self) -> Option<InstalledApp> {
-        let mut path = INSTALL_DIR.clone();
-        path.push(&self.name);
-        if path.exists() {
-            Some(InstalledApp::from_name(&self.name))
+        let app = InstalledApp::from_name(&self.name);
+        if app.is_installed().await {
+            Some(app)
         } else {
             None
         }
@@ -0,0 +1,273 @@
+//! Files downloaded to [`CACHE_DIR`]. They are named `<app>-<version>-<sanitized url>`, and
+//! incomplete downloads have the `.part` suffix.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{dir::CACHE_DIR, error::Result, manifest::DownloadUrl};
+
+#[cfg(test)]
+mod test;
+
+/// Suffix of incomplete downloads
+pub const PART_SUFFIX: &str = ".part";
+
+/// Url schemes a sanitized url in the file name starts with
+const URL_SCHEMES: &[&str] = &["https", "http", "ftp"];
+
+pub struct CachedFile {
+    pub app: String,
+    pub version: String,
+    /// Url of the file, sanitized to be a file name
+    pub url: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    /// Incomplete download, which is resumed by the next download
+    pub partial: bool,
+}
+
+impl CachedFile {
+    /// Time since the file was last modified
+    pub fn age(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.modified).unwrap_or_default()
+    }
+}
+
+/// Version used in the cache file name. Nightly versions are cached per day.
+pub fn cache_version(version: &str) -> String {
+    if version == "nightly" {
+        format!("nightly-{}", chrono::Utc::now().format("%Y-%m-%d"))
+    } else {
+        version.to_string()
+    }
+}
+
+/// Path of the cached file of `url`
+pub fn cache_path(app_name: &str, version: &str, url: &DownloadUrl) -> PathBuf {
+    CACHE_DIR.join(cache_file_name(app_name, &cache_version(version), url))
+}
+
+/// Name of the cached file of `url`, for the version returned by [`cache_version`]
+pub fn cache_file_name(app_name: &str, cache_version: &str, url: &DownloadUrl) -> String {
+    format!(
+        "{}-{}-{}",
+        app_name,
+        cache_version,
+        sanitize_filename::sanitize(&url.url)
+    )
+}
+
+/// Whether `file_name` in the cache is the download of `url` of the app version. Nightly
+/// versions match the download of any day.
+pub fn is_cache_of(file_name: &str, app_name: &str, version: &str, url: &DownloadUrl) -> bool {
+    if version != "nightly" {
+        return file_name == cache_file_name(app_name, version, url);
+    }
+    const DATE_LEN: usize = "YYYY-MM-DD".len();
+    let prefix = format!("{}-nightly-", app_name);
+    let suffix = format!("-{}", sanitize_filename::sanitize(&url.url));
+    file_name.len() == prefix.len() + DATE_LEN + suffix.len()
+        && file_name.starts_with(&prefix)
+        && file_name.ends_with(&suffix)
+}
+
+/// Split the name of a cached file into app, version and sanitized url.
+///
+/// Both app names and versions can contain `-`, so the version is taken to start at the first
+/// `-` followed by a digit, except for nightly versions (`nightly-YYYY-MM-DD`). This is wrong
+/// for app names with `-<digit>`, so it is only good for showing files, not for telling which
+/// files belong to a version.
+pub fn parse_file_name(name: &str) -> Option<(String, String, String)> {
+    let url_start = url_start(name)?;
+    let (app_version, url) = (&name[..url_start], &name[url_start + 1..]);
+
+    let version_start = nightly_version_start(app_version)
+        .or_else(|| {
+            app_version.char_indices().find_map(|(i, c)| {
+                let next = app_version[i + 1..].chars().next();
+                (c == '-' && next.is_some_and(|n| n.is_ascii_digit())).then_some(i)
+            })
+        })
+        .or_else(|| app_version.rfind('-'))?;
+    let (app, version) = (
+        &app_version[..version_start],
+        &app_version[version_start + 1..],
+    );
+    if app.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((app.to_string(), version.to_string(), url.to_string()))
+}
+
+/// Version of the cached file `name` if it is a download of `app`, i.e. it is named
+/// `<app>-<version>-<url>`. The name of another app can start with `<app>-`, e.g. `app-extras`
+/// for `app`, so a file that can be of one of `other_apps` is only taken as of `app` if the
+/// version is one of its known `versions`. A known `nightly` version matches the download of any
+/// day. Unlike [`parse_file_name`], this tells which files belong to an app.
+pub fn version_of(
+    name: &str,
+    app: &str,
+    versions: &[String],
+    other_apps: &[String],
+) -> Option<String> {
+    let name = name.strip_suffix(PART_SUFFIX).unwrap_or(name);
+    let rest = name.strip_prefix(app)?.strip_prefix('-')?;
+    let version = &rest[..url_start(rest)?];
+    if version.is_empty() {
+        return None;
+    }
+
+    let is_known = versions.iter().any(|known| {
+        known == version
+            || (known == "nightly" && nightly_version_start(&format!("-{}", version)) == Some(0))
+    });
+    let of_other_app = || {
+        let starts_with = |s: &str, prefix: &str| {
+            s.strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('-'))
+        };
+        other_apps
+            .iter()
+            .any(|other| starts_with(other, app) && starts_with(name, other))
+    };
+    (is_known || !of_other_app()).then(|| version.to_string())
+}
+
+/// Position of the `-` before the sanitized url in the name of a cached file
+fn url_start(name: &str) -> Option<usize> {
+    name.char_indices().find_map(|(i, c)| {
+        let rest = &name[i + 1..];
+        (c == '-' && URL_SCHEMES.iter().any(|s| rest.starts_with(s))).then_some(i)
+    })
+}
+
+/// Position of the `-` before `nightly-YYYY-MM-DD` at the end of `app_version`
+fn nightly_version_start(app_version: &str) -> Option<usize> {
+    const DATE_LEN: usize = "YYYY-MM-DD".len();
+    let (rest, date) = app_version.split_at(app_version.len().checked_sub(DATE_LEN)?);
+    let is_date = date.chars().enumerate().all(|(i, c)| {
+        if i == 4 || i == 7 {
+            c == '-'
+        } else {
+            c.is_ascii_digit()
+        }
+    });
+    let app = rest.strip_suffix("-nightly-")?;
+    is_date.then_some(app.len())
+}
+
+/// Files in [`CACHE_DIR`], sorted by app and version
+pub async fn cached_files() -> Result<Vec<CachedFile>> {
+    cached_files_in(&CACHE_DIR).await
+}
+
+/// Files in the cache directory `dir`. Files not named by the downloader are ignored.
+pub async fn cached_files_in(dir: &Path) -> Result<Vec<CachedFile>> {
+    let mut files = Vec::new();
+    let mut readdir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = readdir.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let Some(name) = entry.file_name().to_str().map(|n| n.to_string()) else {
+            continue;
+        };
+        let (name, partial) = match name.strip_suffix(PART_SUFFIX) {
+            Some(name) => (name, true),
+            None => (name.as_str(), false),
+        };
+        let Some((app, version, url)) = parse_file_name(name) else {
+            continue;
+        };
+        files.push(CachedFile {
+            app,
+            version,
+            url,
+            path: entry.path(),
+            size: metadata.len(),
+            modified: metadata.modified()?,
+            partial,
+        });
+    }
+    files.sort_by(|a, b| (&a.app, &a.version, &a.url).cmp(&(&b.app, &b.version, &b.url)));
+    Ok(files)
+}
+
+pub fn total_size(files: &[CachedFile]) -> u64 {
+    files.iter().map(|f| f.size).sum()
+}
+
+/// Files to remove so that no file is older than `older_than`, and the cache is at most
+/// `max_size` bytes. The oldest files are removed first, and files for which `keep` returns
+/// true are never removed, even if the cache stays larger than `max_size` because of them.
+pub fn files_to_prune(
+    files: &[CachedFile],
+    keep: impl Fn(&CachedFile) -> bool,
+    max_size: Option<u64>,
+    older_than: Option<Duration>,
+    now: SystemTime,
+) -> Vec<&CachedFile> {
+    let mut candidates = files.iter().filter(|f| !keep(f)).collect::<Vec<_>>();
+    candidates.sort_by_key(|f| f.modified);
+
+    let mut size = total_size(files);
+    let mut prune = Vec::new();
+    for file in candidates {
+        let too_old = older_than.is_some_and(|max_age| file.age(now) > max_age);
+        let too_large = max_size.is_some_and(|max_size| size > max_size);
+        if too_old || too_large {
+            size -= file.size;
+            prune.push(file);
+        }
+    }
+    prune
+}
+
+/// Parse a size like `500MB`, `1.5G` or `1024`. Units are powers of 1024, and `B`/`iB` after
+/// them is optional.
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let number_end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(number_end);
+    let number = number.parse::<f64>().ok()?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit
+        .strip_suffix("IB")
+        .or_else(|| unit.strip_suffix('B'))
+        .unwrap_or(&unit);
+    let exponent = ["", "K", "M", "G", "T"].iter().position(|u| *u == unit)?;
+    Some((number * 1024f64.powi(exponent as i32)) as u64)
+}
+
+/// Parse an age like `30d`, `12h`, `2w`, `45m` or `90s`
+pub fn parse_age(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit())?);
+    let number = number.parse::<u64>().ok()?;
+    let secs = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(number * secs))
+}
+
+/// Format an age with its largest unit, like `3d`
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    str::FromStr as _,
+    time::{Duration, SystemTime},
+};
+
+use super::*;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn parsed(name: &str) -> Option<(String, String, String)> {
+    parse_file_name(name)
+}
+
+fn parts(app: &str, version: &str, url: &str) -> Option<(String, String, String)> {
+    Some((app.to_string(), version.to_string(), url.to_string()))
+}
+
+#[test]
+fn parse_cache_file_names() {
+    assert_eq!(
+        parsed("7zip-23.01-httpswww.7-zip.orga7z2301-x64.msi"),
+        parts("7zip", "23.01", "httpswww.7-zip.orga7z2301-x64.msi")
+    );
+    assert_eq!(
+        parsed("vscode-insiders-1.89.0-insider-httpsupdate.code.visualstudio.comlatest.zip"),
+        parts(
+            "vscode-insiders",
+            "1.89.0-insider",
+            "httpsupdate.code.visualstudio.comlatest.zip"
+        )
+    );
+    assert_eq!(
+        parsed("ffmpeg-nightly-nightly-2024-05-01-httpexample.comffmpeg.zip"),
+        parts(
+            "ffmpeg-nightly",
+            "nightly-2024-05-01",
+            "httpexample.comffmpeg.zip"
+        )
+    );
+    assert_eq!(
+        parsed("app-v2-ftpexample.comapp.zip"),
+        parts("app", "v2", "ftpexample.comapp.zip")
+    );
+    assert_eq!(parsed("app-1.0"), None);
+    assert_eq!(parsed("-1.0-httpsexample.com"), None);
+}
+
+#[test]
+fn match_files_of_app() {
+    let versions = ["1.0".to_string(), "nightly".to_string()];
+    let other_apps = ["app-extras".to_string(), "app-2".to_string()];
+    let version = |name| version_of(name, "app", &versions, &other_apps);
+
+    assert_eq!(
+        version("app-1.0-httpsexample.comapp.zip"),
+        Some("1.0".to_string())
+    );
+    assert_eq!(
+        version("app-1.0-httpsexample.comapp.zip.part"),
+        Some("1.0".to_string())
+    );
+    assert_eq!(
+        version("app-nightly-2024-05-01-httpsexample.comapp.zip"),
+        Some("nightly-2024-05-01".to_string())
+    );
+    // Versions that are neither installed nor in a bucket anymore
+    assert_eq!(
+        version("app-2.0-httpsexample.comapp.zip"),
+        Some("2.0".to_string())
+    );
+    assert_eq!(
+        version("app-1.0-beta-httpsexample.comapp.zip"),
+        Some("1.0-beta".to_string())
+    );
+    // Files of other apps whose names start with `app-`, unless the version is of `app`
+    assert_eq!(version("app-extras-2.0-httpsexample.comapp.zip"), None);
+    assert_eq!(version("app-2-3.0-httpsexample.comapp.zip"), None);
+    assert_eq!(
+        version_of(
+            "app-2-1.0-httpsexample.comapp.zip",
+            "app",
+            &["2-1.0".to_string()],
+            &other_apps
+        ),
+        Some("2-1.0".to_string())
+    );
+    assert_eq!(
+        version_of(
+            "app-extras-2.0-httpsexample.comapp.zip",
+            "app-extras",
+            &[],
+            &["app".to_string()]
+        ),
+        Some("2.0".to_string())
+    );
+    // Not named after the app
+    assert_eq!(version("application-1.0-httpsexample.comapp.zip"), None);
+    assert_eq!(version("app-httpsexample.comapp.zip"), None);
+    assert_eq!(version("app-1.0"), None);
+}
+
+#[test]
+fn cache_files_of_versions() {
+    let url = DownloadUrl::from_str("https://example.com/app-2.0.zip#/app.zip").unwrap();
+    let name = |app, version| {
+        cache_path(app, version, &url)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    };
+
+    // Parsing the name would take this for version 2-1.0 of `app`
+    let file = name("app-2", "1.0");
+    assert!(is_cache_of(&file, "app-2", "1.0", &url));
+    assert!(!is_cache_of(&file, "app-2", "1.1", &url));
+    let other = DownloadUrl::from_str("https://example.com/other.zip").unwrap();
+    assert!(!is_cache_of(&file, "app-2", "1.0", &other));
+
+    // Nightly versions are cached per day
+    let nightly = name("app", "nightly");
+    assert!(is_cache_of(&nightly, "app", "nightly", &url));
+    let old = cache_file_name("app", "nightly-2020-01-01", &url);
+    assert!(is_cache_of(&old, "app", "nightly", &url));
+    let other_app = cache_file_name("app-x", "nightly-2020-01-01", &url);
+    assert!(!is_cache_of(&other_app, "app", "nightly", &url));
+    assert!(!is_cache_of(&old, "app", "2020", &url));
+}
+
+fn file(app: &str, version: &str, size: u64, age_days: u32) -> CachedFile {
+    CachedFile {
+        app: app.to_string(),
+        version: version.to_string(),
+        url: "httpsexample.comapp.zip".to_string(),
+        path: PathBuf::from(format!("{}-{}", app, version)),
+        size,
+        modified: SystemTime::UNIX_EPOCH + DAY * 100 - DAY * age_days,
+        partial: false,
+    }
+}
+
+fn names<'a>(files: &[&'a CachedFile]) -> Vec<(&'a str, &'a str)> {
+    files
+        .iter()
+        .map(|f| (f.app.as_str(), f.version.as_str()))
+        .collect()
+}
+
+#[test]
+fn prune_by_size_and_age() {
+    let now = SystemTime::UNIX_EPOCH + DAY * 100;
+    let files = [
+        file("a", "1.0", 100, 30),
+        file("a", "2.0", 100, 1),
+        file("b", "1.0", 100, 10),
+        file("c", "1.0", 100, 20),
+    ];
+    let keep_none = |_: &CachedFile| false;
+
+    let pruned = files_to_prune(&files, keep_none, Some(250), None, now);
+    assert_eq!(names(&pruned), [("a", "1.0"), ("c", "1.0")]);
+
+    let pruned = files_to_prune(&files, keep_none, None, Some(DAY * 15), now);
+    assert_eq!(names(&pruned), [("a", "1.0"), ("c", "1.0")]);
+
+    let pruned = files_to_prune(&files, keep_none, Some(300), Some(DAY * 25), now);
+    assert_eq!(names(&pruned), [("a", "1.0")]);
+
+    assert!(files_to_prune(&files, keep_none, Some(400), None, now).is_empty());
+}
+
+#[test]
+fn prune_keeps_installed_versions() {
+    let now = SystemTime::UNIX_EPOCH + DAY * 100;
+    let files = [
+        file("a", "1.0", 100, 30),
+        file("b", "1.0", 100, 10),
+        file("c", "1.0", 100, 20),
+    ];
+    let installed = |f: &CachedFile| f.app == "a" && f.version == "1.0";
+
+    let pruned = files_to_prune(&files, installed, Some(0), None, now);
+    assert_eq!(names(&pruned), [("c", "1.0"), ("b", "1.0")]);
+}
+
+#[tokio::test]
+async fn list_cache_dir() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("b-1.0-httpsexample.comb.zip"), b"b").unwrap();
+    std::fs::write(tmp.path().join("a-2.0-httpsexample.coma.zip.part"), b"aa").unwrap();
+    std::fs::write(tmp.path().join("unknown.txt"), b"?").unwrap();
+    std::fs::create_dir(tmp.path().join("a-1.0-httpsexample.comdir")).unwrap();
+
+    let files = cached_files_in(tmp.path()).await.unwrap();
+    let listed = files
+        .iter()
+        .map(|f| (f.app.as_str(), f.version.as_str(), f.size, f.partial))
+        .collect::<Vec<_>>();
+    assert_eq!(listed, [("a", "2.0", 2, true), ("b", "1.0", 1, false)]);
+    assert_eq!(total_size(&files), 3);
+}
+
+#[test]
+fn parse_sizes() {
+    assert_eq!(parse_size("1024"), Some(1024));
+    assert_eq!(parse_size("10K"), Some(10 * 1024));
+    assert_eq!(parse_size("500MB"), Some(500 * 1024 * 1024));
+    assert_eq!(parse_size("1.5GiB"), Some(1536 * 1024 * 1024));
+    assert_eq!(parse_size("2 gb"), Some(2 * 1024 * 1024 * 1024));
+    assert_eq!(parse_size("10X"), None);
+    assert_eq!(parse_size("MB"), None);
+}
+
+#[test]
+fn parse_and_format_ages() {
+    assert_eq!(parse_age("30d"), Some(DAY * 30));
+    assert_eq!(parse_age("2w"), Some(DAY * 14));
+    assert_eq!(parse_age("12h"), Some(Duration::from_secs(12 * 60 * 60)));
+    assert_eq!(parse_age("30"), None);
+    assert_eq!(parse_age("d"), None);
+
+    assert_eq!(format_age(Duration::from_secs(59)), "59s");
+    assert_eq!(format_age(Duration::from_secs(90 * 60)), "1h");
+    assert_eq!(format_age(DAY * 3), "3d");
+}
@@ -0,0 +1,96 @@
+//! Settings in [`CONFIG_FILE`]. Every setting is optional, so a missing file is the same as an
+//! empty one.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    dir::{CONFIG_FILE, SHORTCUTS_DIR},
+    error::{Error, Result},
+};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Extra headers sent with downloads, by host. For example
+    /// `{"artifacts.example.com": {"Authorization": "Bearer <token>"}}`
+    pub headers: HashMap<String, HashMap<String, String>>,
+    /// Start menu folder for shortcuts of apps, [`SHORTCUTS_DIR`] if not set
+    pub shortcuts_dir: Option<PathBuf>,
+    #[serde(flatten)]
+    pub network: NetworkConfig,
+}
+
+/// Settings applied to all network traffic, both downloads and git
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy url like `http://proxy.example.com:8080`. The proxy from the environment is used if
+    /// this is not set.
+    pub proxy: Option<String>,
+    /// Hosts that are connected to without `proxy`. `example.com` also matches its subdomains,
+    /// and `*` matches all hosts.
+    pub no_proxy: Vec<String>,
+    /// PEM file of CA certificates trusted in addition to the system ones
+    pub ca_bundle: Option<PathBuf>,
+    /// Hosts of private buckets that the token in [`crate::bucket::TOKEN_VAR`] is sent to.
+    /// It is only ever sent over HTTPS.
+    pub git_token_hosts: Vec<String>,
+    /// Don't verify TLS certificates. This can't be set in the file, only with `--insecure`.
+    #[serde(skip)]
+    pub insecure: bool,
+}
+
+impl NetworkConfig {
+    /// Proxy for requests to `host`, if any
+    pub fn proxy_for(&self, host: &str) -> Option<&str> {
+        let proxy = self.proxy.as_deref()?;
+        let host = host.to_ascii_lowercase();
+        let bypass = self.no_proxy.iter().any(|pattern| {
+            let pattern = pattern.trim().trim_start_matches('.').to_ascii_lowercase();
+            pattern == "*"
+                || host == pattern
+                || host
+                    .strip_suffix(&pattern)
+                    .is_some_and(|rest| rest.ends_with('.'))
+        });
+        (!bypass).then_some(proxy)
+    }
+}
+
+impl Config {
+    pub async fn load() -> Result<Self> {
+        Self::from_path(&CONFIG_FILE).await
+    }
+
+    pub async fn from_path(path: &Path) -> Result<Self> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&content).map_err(|e| Error::JsonParse("config.json", e))
+    }
+
+    /// Folder for start menu shortcuts of apps
+    pub fn shortcuts_dir(&self) -> PathBuf {
+        self.shortcuts_dir
+            .clone()
+            .unwrap_or_else(|| SHORTCUTS_DIR.clone())
+    }
+
+    /// Extra headers for requests to `host`. Hosts are compared case-insensitively.
+    pub fn headers_for<'a>(&'a self, host: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.headers
+            .iter()
+            .filter(move |(h, _)| h.eq_ignore_ascii_case(host))
+            .flat_map(|(_, headers)| headers.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    }
+}
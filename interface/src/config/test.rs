@@ -0,0 +1,84 @@
+use super::{Config, NetworkConfig};
+
+#[tokio::test]
+async fn missing_config_is_empty() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = Config::from_path(&tmp.path().join("config.json"))
+        .await
+        .unwrap();
+    assert!(config.headers.is_empty());
+}
+
+#[tokio::test]
+async fn headers_by_host() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("config.json");
+    std::fs::write(
+        &path,
+        r#"{"headers": {"Artifacts.example.com": {"Authorization": "Bearer token"}}}"#,
+    )
+    .unwrap();
+
+    let config = Config::from_path(&path).await.unwrap();
+    assert_eq!(
+        config
+            .headers_for("artifacts.example.com")
+            .collect::<Vec<_>>(),
+        [("Authorization", "Bearer token")]
+    );
+    assert_eq!(config.headers_for("example.com").count(), 0);
+
+    std::fs::write(&path, r#"{"headers": []}"#).unwrap();
+    assert!(matches!(
+        Config::from_path(&path).await,
+        Err(crate::Error::JsonParse("config.json", _))
+    ));
+}
+
+#[tokio::test]
+async fn network_settings() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("config.json");
+    std::fs::write(
+        &path,
+        r#"{
+            "proxy": "http://proxy.example.com:8080",
+            "no_proxy": ["internal.example.com", ".local"],
+            "ca_bundle": "C:/certs/root.pem",
+            "insecure": true
+        }"#,
+    )
+    .unwrap();
+
+    let network = Config::from_path(&path).await.unwrap().network;
+    assert_eq!(
+        network.ca_bundle.unwrap().to_str(),
+        Some("C:/certs/root.pem")
+    );
+    // Only `--insecure` disables verification
+    assert!(!network.insecure);
+}
+
+#[test]
+fn no_proxy_hosts() {
+    let network = NetworkConfig {
+        proxy: Some("http://proxy:8080".to_string()),
+        no_proxy: vec!["internal.example.com".to_string(), ".local".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(network.proxy_for("github.com"), Some("http://proxy:8080"));
+    assert_eq!(network.proxy_for("internal.example.com"), None);
+    assert_eq!(network.proxy_for("git.Internal.example.com"), None);
+    assert_eq!(
+        network.proxy_for("notinternal.example.com"),
+        Some("http://proxy:8080")
+    );
+    assert_eq!(network.proxy_for("server.local"), None);
+
+    let bypass_all = NetworkConfig {
+        no_proxy: vec!["*".to_string()],
+        ..network.clone()
+    };
+    assert_eq!(bypass_all.proxy_for("github.com"), None);
+    assert_eq!(NetworkConfig::default().proxy_for("github.com"), None);
+}
@@ -12,6 +12,9 @@ pub static INSTALL_DIR: Lazy<PathBuf> = Lazy::new(|| {
     }
 });
 
+/// Settings of scoop-rs. See [`crate::config::Config`].
+pub static CONFIG_FILE: Lazy<PathBuf> = Lazy::new(|| INSTALL_DIR.join("config.json"));
+
 pub static BUCKETS_DIR: Lazy<PathBuf> = Lazy::new(|| {
     let mut path = INSTALL_DIR.clone();
     path.push("buckets");
@@ -29,3 +32,32 @@ pub static CACHE_DIR: Lazy<PathBuf> = Lazy::new(|| {
     }
     path
 });
+
+pub static PERSIST_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let mut path = INSTALL_DIR.clone();
+    path.push("persist");
+    if !path.exists() {
+        std::fs::create_dir_all(&path).expect("Failed to create persist directory");
+    }
+    path
+});
+
+pub static SHIMS_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let mut path = INSTALL_DIR.clone();
+    path.push("shims");
+    if !path.exists() {
+        std::fs::create_dir_all(&path).expect("Failed to create shims directory");
+    }
+    path
+});
+
+/// `Scoop Apps` folder in the start menu
+pub static SHORTCUTS_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let mut path = dirs::data_dir().expect("Failed to get data directory");
+    path.push("Microsoft");
+    path.push("Windows");
+    path.push("Start Menu");
+    path.push("Programs");
+    path.push("Scoop Apps");
+    path
+});
@@ -6,8 +6,41 @@ pub enum Error {
     ManifestParse(#[source] serde_json::Error),
     #[error("Failed to parse {0} json:\n\t{1}")]
     JsonParse(&'static str, #[source] serde_json::Error),
+    #[error("Invalid hash:\n\t{0}")]
+    InvalidHash(String),
+    #[error("Manifest has {hashes} hashes for {urls} urls")]
+    HashCountMismatch { urls: usize, hashes: usize },
+    #[error("Zip error:\n\t{0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("7z error:\n\t{0}")]
+    SevenZip(#[from] sevenz_rust::Error),
+    #[error("MSI error:\n\t{0}")]
+    Msi(String),
+    #[error("InnoSetup error:\n\t{0}")]
+    Inno(String),
+    #[error("InnoSetup version {0} is not supported")]
+    UnsupportedInnoVersion(String),
+    #[error("`{0}` is not found in the archive")]
+    ExtractDirNotFound(String),
+    #[error("`extract_to` `{0}` points outside of the app directory")]
+    UnsafeExtractTo(String),
+    #[error("Refused to extract entries pointing outside of the destination:\n\t{}", .0.join("\n\t"))]
+    UnsafeArchiveEntries(Vec<String>),
     #[error("Invalid state:\n\t{0}")]
     InvalidState(String),
+    #[error("`{0}` is not a valid bucket name")]
+    InvalidBucketName(String),
+    #[error("Bucket `{0}` already exists")]
+    BucketAlreadyExists(String),
+    #[error("Bucket `{0}` has diverged from its remote")]
+    BucketDiverged(String),
+    #[error("Bucket `{0}` has uncommitted changes")]
+    BucketDirty(String),
+    #[error(
+        "`ca_bundle` is not supported for buckets on Windows, add its certificates to the \
+         system or use --insecure"
+    )]
+    UnsupportedCaBundle,
     #[error("Git error:\n\t{0}")]
     Git(#[from] git2::Error),
     #[error("{0}:\n\t{1}")]
@@ -0,0 +1,412 @@
+//! Extraction of downloaded archives.
+//! Everything is implemented in pure Rust, so this works on any platform.
+
+use std::{
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::error::{Error, Result};
+
+mod inno;
+mod msi;
+mod sevenz;
+mod single;
+mod tar;
+#[cfg(test)]
+mod test;
+mod zip;
+
+/// Name of the temporary directory used when `extract_dir` is specified. Same as Scoop.
+const STAGING_DIR: &str = "_tmp";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    SevenZip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarBz2,
+    TarZst,
+    /// Single gzip compressed file
+    Gz,
+    /// Windows Installer package
+    Msi,
+    /// InnoSetup installer. Only the files installed to `{app}` are extracted.
+    /// It is a plain `.exe`, so this is never detected from the file name.
+    InnoSetup,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the file name. Returns `None` if the file is not an archive.
+    pub fn detect(file_name: &str) -> Option<Self> {
+        const EXTENSIONS: &[(&str, ArchiveFormat)] = &[
+            (".tar.gz", ArchiveFormat::TarGz),
+            (".tgz", ArchiveFormat::TarGz),
+            (".tar.xz", ArchiveFormat::TarXz),
+            (".txz", ArchiveFormat::TarXz),
+            (".tar.bz2", ArchiveFormat::TarBz2),
+            (".tbz2", ArchiveFormat::TarBz2),
+            (".tbz", ArchiveFormat::TarBz2),
+            (".tar.zst", ArchiveFormat::TarZst),
+            (".tzst", ArchiveFormat::TarZst),
+            (".tar", ArchiveFormat::Tar),
+            (".gz", ArchiveFormat::Gz),
+            (".zip", ArchiveFormat::Zip),
+            (".nupkg", ArchiveFormat::Zip),
+            (".7z", ArchiveFormat::SevenZip),
+            (".msi", ArchiveFormat::Msi),
+        ];
+
+        let file_name = file_name.to_ascii_lowercase();
+        EXTENSIONS
+            .iter()
+            .find(|(ext, _)| file_name.ends_with(ext))
+            .map(|(_, format)| *format)
+    }
+}
+
+/// Extract `file` into `dest`, or copy it there if it is not an archive.
+/// `file_name` is used to detect the format (e.g. the `#/` rename of the url), and
+/// if `extract_dir` is given, only the contents of that directory in the archive are extracted.
+///
+/// NOTE: This is a blocking function
+pub fn extract(file: &Path, file_name: &str, dest: &Path, extract_dir: Option<&str>) -> Result<()> {
+    extract_as(
+        file,
+        file_name,
+        ArchiveFormat::detect(file_name),
+        dest,
+        extract_dir,
+    )
+}
+
+/// Same as [`extract`], but with the format given explicitly. `None` copies the file.
+///
+/// NOTE: This is a blocking function
+pub fn extract_as(
+    file: &Path,
+    file_name: &str,
+    format: Option<ArchiveFormat>,
+    dest: &Path,
+    extract_dir: Option<&str>,
+) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+
+    let Some(format) = format else {
+        std::fs::copy(file, dest.join(file_name))?;
+        return Ok(());
+    };
+
+    let extract_dir = extract_dir.filter(|d| !d.is_empty());
+    let Some(extract_dir) = extract_dir else {
+        return unpack(file, file_name, format, dest)?.verify(dest);
+    };
+    // `extract_dir` comes from the manifest, but it must not point outside of the archive either
+    let Some(extract_dir_path) = relative_path(extract_dir) else {
+        return Err(Error::ExtractDirNotFound(extract_dir.to_string()));
+    };
+
+    let staging = dest.join(STAGING_DIR);
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    let res = unpack(file, file_name, format, &staging).and_then(|unpacked| {
+        let src = staging.join(&extract_dir_path);
+        if !src.is_dir() {
+            return Err(Error::ExtractDirNotFound(extract_dir.to_string()));
+        }
+        // Only `extract_dir` is moved to `dest`, so symlinks must stay inside of it
+        unpacked.verify(&src)?;
+        move_dir_contents(&src, dest)
+    });
+    std::fs::remove_dir_all(&staging)?;
+    res
+}
+
+/// Directory in the app directory `dir` that a url with `extract_to` is extracted to.
+/// Like `extract_dir`, `extract_to` comes from the manifest and must not point outside of `dir`.
+pub fn extract_to_path(dir: &Path, extract_to: &str) -> Result<PathBuf> {
+    match relative_path(extract_to) {
+        Some(path) if resolves_inside(dir, &path) => Ok(dir.join(path)),
+        _ => Err(Error::UnsafeExtractTo(extract_to.to_string())),
+    }
+}
+
+fn unpack(file: &Path, file_name: &str, format: ArchiveFormat, dest: &Path) -> Result<Unpacked> {
+    std::fs::create_dir_all(dest)?;
+    let mut unpacker = Unpacker::new(dest);
+    match format {
+        ArchiveFormat::Zip => zip::unpack(file, &mut unpacker)?,
+        ArchiveFormat::SevenZip => sevenz::unpack(file, &mut unpacker)?,
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarBz2
+        | ArchiveFormat::TarZst => tar::unpack(file, format, &mut unpacker)?,
+        ArchiveFormat::Gz => single::unpack_gz(file, file_name, &mut unpacker)?,
+        ArchiveFormat::Msi => msi::unpack(file, &mut unpacker)?,
+        ArchiveFormat::InnoSetup => inno::unpack(file, &mut unpacker)?,
+    }
+    unpacker.finish()
+}
+
+/// Normalize the archive entry `name` to a path relative to the destination.
+/// Returns `None` for absolute paths and paths that leave the destination with `..`.
+fn relative_path(name: &str) -> Option<PathBuf> {
+    let name = name.replace('\\', "/");
+    if name.starts_with('/') {
+        return None;
+    }
+    let mut components = Vec::new();
+    for component in name.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            // Drive letters (`C:`) and NTFS alternate data streams
+            c if c.contains(':') => return None,
+            c => components.push(c),
+        }
+    }
+    Some(components.iter().collect())
+}
+
+/// Writes archive entries into `dest`, rejecting entries that would end up outside of it.
+///
+/// Symlinks are created after all other entries, so no file is ever written through a symlink
+/// from the archive. A symlink is only created if no symlink is in its parent path and its
+/// target is inside `dest`. Where they point to is checked again by [`Unpacked::verify`] once all
+/// of them exist, because a symlink can point to another one.
+struct Unpacker<'a> {
+    dest: &'a Path,
+    symlinks: Vec<(String, PathBuf, String)>,
+    rejected: Vec<String>,
+}
+
+impl<'a> Unpacker<'a> {
+    fn new(dest: &'a Path) -> Self {
+        Unpacker {
+            dest,
+            symlinks: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Path in `dest` to write the entry `name` to, or `None` if the entry is rejected
+    fn entry_path(&mut self, name: &str) -> Option<PathBuf> {
+        match relative_path(name) {
+            Some(path) => Some(self.dest.join(path)),
+            None => {
+                self.rejected.push(name.to_string());
+                None
+            }
+        }
+    }
+
+    fn dir(&mut self, name: &str) -> Result<()> {
+        if let Some(path) = self.entry_path(name) {
+            std::fs::create_dir_all(path)?;
+        }
+        Ok(())
+    }
+
+    /// Write the entry `name`. Returns the written path, if the entry is not rejected.
+    fn file(&mut self, name: &str, reader: &mut dyn Read) -> Result<Option<PathBuf>> {
+        let Some(path) = self.entry_path(name) else {
+            return Ok(None);
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::File::create(&path)?;
+        std::io::copy(reader, &mut file)?;
+        Ok(Some(path))
+    }
+
+    /// Hard link to the entry `target`, extracted before. It is extracted as a copy.
+    fn hard_link(&mut self, name: &str, target: &str) -> Result<()> {
+        let (Some(path), Some(target_path)) = (self.entry_path(name), relative_path(target)) else {
+            self.rejected.push(format!("{} => {}", name, target));
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(self.dest.join(target_path), path)?;
+        Ok(())
+    }
+
+    fn symlink(&mut self, name: &str, target: &str) {
+        let Some(path) = relative_path(name) else {
+            self.rejected.push(format!("{} -> {}", name, target));
+            return;
+        };
+        self.symlinks
+            .push((name.to_string(), path, target.to_string()));
+    }
+
+    fn finish(mut self) -> Result<Unpacked> {
+        let mut symlinks = Vec::new();
+        for (name, path, target) in std::mem::take(&mut self.symlinks) {
+            let entry = format!("{} -> {}", name, target);
+            let target = target.replace('\\', "/");
+            if target.starts_with('/') || target.contains(':') {
+                self.rejected.push(entry);
+                continue;
+            }
+            // A symlink created before must not redirect where this one is created to
+            let parent = path.parent().unwrap_or(Path::new(""));
+            if has_symlink_in(self.dest, parent)
+                || !resolves_inside(self.dest, &parent.join(&target))
+            {
+                self.rejected.push(entry);
+                continue;
+            }
+            let path = self.dest.join(path);
+            create_symlink(&path, &target)?;
+            symlinks.push((entry, path));
+        }
+        Ok(Unpacked {
+            symlinks,
+            rejected: self.rejected,
+        })
+    }
+}
+
+/// Unpacked archive whose symlinks are not checked yet
+#[must_use]
+struct Unpacked {
+    /// Entry description and path of created symlinks
+    symlinks: Vec<(String, PathBuf)>,
+    rejected: Vec<String>,
+}
+
+impl Unpacked {
+    /// Remove symlinks under `root` that resolve outside of it, and fail if any entry was rejected
+    fn verify(mut self, root: &Path) -> Result<()> {
+        for (entry, path) in &self.symlinks {
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            if !resolves_inside(root, relative) {
+                remove_symlink(path)?;
+                self.rejected.push(entry.clone());
+            }
+        }
+
+        if self.rejected.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::UnsafeArchiveEntries(self.rejected))
+        }
+    }
+}
+
+/// Whether `path` (relative to `root`) stays inside `root`, following symlinks on the
+/// file system. Components that don't exist are resolved lexically.
+fn resolves_inside(root: &Path, path: &Path) -> bool {
+    /// Same as the limit of Linux
+    const MAX_SYMLINKS: usize = 40;
+
+    let mut resolved = PathBuf::new();
+    let mut pending = path
+        .components()
+        .rev()
+        .map(|c| c.as_os_str().to_os_string())
+        .collect::<Vec<_>>();
+    let mut followed = 0;
+    while let Some(component) = pending.pop() {
+        if component == ".." {
+            if !resolved.pop() {
+                return false;
+            }
+            continue;
+        }
+        if component == "." {
+            continue;
+        }
+        resolved.push(&component);
+
+        let current = root.join(&resolved);
+        if !current.is_symlink() {
+            continue;
+        }
+        followed += 1;
+        if followed > MAX_SYMLINKS {
+            return false;
+        }
+        let Ok(target) = std::fs::read_link(&current) else {
+            return false;
+        };
+        if target
+            .components()
+            .any(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+        {
+            return false;
+        }
+        resolved.pop();
+        pending.extend(
+            target
+                .components()
+                .rev()
+                .map(|c| c.as_os_str().to_os_string()),
+        );
+    }
+    true
+}
+
+/// Whether any component of `path` (relative to `root`) is a symlink
+fn has_symlink_in(root: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| std::fs::symlink_metadata(root.join(p)).is_ok_and(|m| m.file_type().is_symlink()))
+}
+
+fn create_symlink(path: &Path, target: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, path)?;
+    #[cfg(windows)]
+    {
+        let target = target.replace('/', "\\");
+        if path.parent().map(|p| p.join(&target).is_dir()) == Some(true) {
+            std::os::windows::fs::symlink_dir(target, path)?;
+        } else {
+            std::os::windows::fs::symlink_file(target, path)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_symlink(path: &Path) -> Result<()> {
+    // Directory symlinks on Windows have to be removed as directories
+    if std::fs::remove_file(path).is_err() {
+        std::fs::remove_dir(path)?;
+    }
+    Ok(())
+}
+
+/// Move all entries in `src` into `dest`, merging directories that exist in both
+fn move_dir_contents(src: &Path, dest: &Path) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() && target.is_dir() {
+            move_dir_contents(&entry.path(), &target)?;
+        } else {
+            if target.is_dir() {
+                std::fs::remove_dir_all(&target)?;
+            } else if target.symlink_metadata().is_ok() {
+                std::fs::remove_file(&target)?;
+            }
+            std::fs::rename(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
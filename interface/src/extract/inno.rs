@@ -0,0 +1,58 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use ::inno::{error::InnoError, Inno};
+
+use crate::error::{Error, Result};
+
+use super::Unpacker;
+
+/// Constant of the installation directory in InnoSetup destinations
+const APP_DIR: &str = "{app}";
+
+/// Extract the files an InnoSetup installer installs to `{app}`, same as `innounp -x -c{app}`.
+/// Files for other destinations (`{tmp}`, `{sys}`, ...) are not part of the app and skipped.
+pub(super) fn unpack(file: &Path, unpacker: &mut Unpacker) -> Result<()> {
+    let mut inno = Inno::new(BufReader::new(File::open(file)?)).map_err(inno_error)?;
+
+    let mut files = inno.streaming_files(|entry| {
+        entry
+            .file()
+            .destination()
+            .and_then(app_relative_path)
+            .is_some()
+    });
+    while let Some(res) = files.next() {
+        let (entry, mut reader) = res.map_err(inno_error)?;
+        let Some(name) = entry.file().destination().and_then(app_relative_path) else {
+            continue;
+        };
+        // Later entries with the same destination overwrite earlier ones, as innounp does
+        unpacker.file(name, &mut reader)?;
+    }
+    Ok(())
+}
+
+/// Path of `destination` relative to `{app}`, e.g. `bin\app.exe` for `{app}\bin\app.exe`
+fn app_relative_path(destination: &str) -> Option<&str> {
+    let (constant, path) = destination.split_once('\\')?;
+    constant.eq_ignore_ascii_case(APP_DIR).then_some(path)
+}
+
+fn inno_error(e: InnoError) -> Error {
+    match e {
+        InnoError::UnsupportedVersion(version) => {
+            Error::UnsupportedInnoVersion(version.to_string())
+        }
+        InnoError::UnknownVersion(raw) => {
+            // e.g. `Inno Setup Setup Data (5.5.7)` padded with NUL
+            let raw = raw.trim_end_matches('\0');
+            let version = raw
+                .split_once('(')
+                .and_then(|(_, v)| v.split_once(')'))
+                .map_or(raw, |(v, _)| v);
+            Error::UnsupportedInnoVersion(version.to_string())
+        }
+        InnoError::Io(e) => Error::Io(e),
+        e => Error::Inno(e.to_string()),
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Cursor, Read as _},
+    path::Path,
+};
+
+use crate::error::{Error, Result};
+
+use super::Unpacker;
+
+/// Extract files of a MSI package, with the same layout as lessmsi.
+///
+/// Directories come from the `Directory` table, files are placed in the directory of their
+/// component, and their contents are read from the cabinets listed in the `Media` table.
+/// The root directory (`SourceDir` in lessmsi) is not included in the paths.
+pub(super) fn unpack(file: &Path, unpacker: &mut Unpacker) -> Result<()> {
+    let mut package = ::msi::Package::open(std::fs::File::open(file)?)?;
+
+    let directories = directories(&mut package)?;
+    let mut component_dirs = HashMap::new();
+    for row in package.select_rows(::msi::Select::table("Component"))? {
+        component_dirs.insert(string(&row, "Component"), string(&row, "Directory_"));
+    }
+
+    let mut files = HashMap::new();
+    for row in package.select_rows(::msi::Select::table("File"))? {
+        let dir = component_dirs
+            .get(&string(&row, "Component_"))
+            .and_then(|dir| directories.get(dir))
+            .ok_or_else(|| {
+                Error::Msi(format!(
+                    "Directory of file `{}` is not found",
+                    string(&row, "File")
+                ))
+            })?;
+        let name = long_name(&string(&row, "FileName")).to_string();
+        let path = if dir.is_empty() {
+            name
+        } else {
+            format!("{}/{}", dir, name)
+        };
+        files.insert(string(&row, "File"), path);
+    }
+
+    let mut cabinets = Vec::new();
+    for row in package.select_rows(::msi::Select::table("Media"))? {
+        let cabinet = string(&row, "Cabinet");
+        if cabinet.is_empty() {
+            continue;
+        }
+        // Cabinets starting with `#` are streams in the package, others are next to it
+        let Some(stream) = cabinet.strip_prefix('#') else {
+            return Err(Error::Msi(format!(
+                "External cabinet `{}` is not supported",
+                cabinet
+            )));
+        };
+        cabinets.push(stream.to_string());
+    }
+
+    let mut extracted = HashSet::new();
+    for stream in cabinets {
+        let mut data = Vec::new();
+        package.read_stream(&stream)?.read_to_end(&mut data)?;
+        let mut cabinet = ::cab::Cabinet::new(Cursor::new(data))?;
+        let names = cabinet
+            .folder_entries()
+            .flat_map(|folder| folder.file_entries().map(|f| f.name().to_string()))
+            .collect::<Vec<_>>();
+        for name in names {
+            // Cabinet entries are named by the key of the `File` table
+            let Some(path) = files.get(&name) else {
+                continue;
+            };
+            unpacker.file(path, &mut cabinet.read_file(&name)?)?;
+            extracted.insert(name);
+        }
+    }
+
+    let mut missing = files
+        .keys()
+        .filter(|key| !extracted.contains(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        missing.sort();
+        return Err(Error::Msi(format!(
+            "Files are not found in the cabinets: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Path of each directory relative to the root directory, separated by `/`
+fn directories(package: &mut ::msi::Package<std::fs::File>) -> Result<HashMap<String, String>> {
+    let mut entries = HashMap::new();
+    for row in package.select_rows(::msi::Select::table("Directory"))? {
+        let id = string(&row, "Directory");
+        let parent = Some(string(&row, "Directory_Parent")).filter(|p| !p.is_empty() && *p != id);
+        // `DefaultDir` is `[target:]source`, and each of them is `[short|]long`
+        let default_dir = string(&row, "DefaultDir");
+        let target = default_dir
+            .split_once(':')
+            .map_or(default_dir.as_str(), |(target, _)| target);
+        entries.insert(id, (parent, long_name(target).to_string()));
+    }
+
+    let mut paths = HashMap::new();
+    for id in entries.keys() {
+        let mut components = Vec::new();
+        let mut current = id;
+        // A directory can't have more ancestors than there are directories, unless there is a cycle
+        for _ in 0..=entries.len() {
+            let (parent, name) = &entries[current];
+            // The root directory is the destination itself
+            let Some(parent) = parent else {
+                components.reverse();
+                paths.insert(id.clone(), components.join("/"));
+                break;
+            };
+            if name != "." {
+                components.push(name.as_str());
+            }
+            let Some((parent, _)) = entries.get_key_value(parent) else {
+                break;
+            };
+            current = parent;
+        }
+        if !paths.contains_key(id) {
+            return Err(Error::Msi(format!("Invalid parent of directory `{}`", id)));
+        }
+    }
+    Ok(paths)
+}
+
+fn string(row: &::msi::Row, column: &str) -> String {
+    row[column].as_str().unwrap_or_default().to_string()
+}
+
+/// Names in MSI tables can be `short|long`
+fn long_name(name: &str) -> &str {
+    name.split_once('|').map_or(name, |(_, long)| long)
+}
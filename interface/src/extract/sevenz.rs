@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use crate::error::Result;
+
+use super::Unpacker;
+
+pub(super) fn unpack(file: &Path, unpacker: &mut Unpacker) -> Result<()> {
+    let dest = unpacker.dest.to_path_buf();
+    sevenz_rust::decompress_file_with_extract_fn(file, dest, |entry, reader, _| {
+        let res = if entry.is_directory() {
+            unpacker.dir(entry.name())
+        } else {
+            unpacker.file(entry.name(), reader).map(|_| ())
+        };
+        res.map_err(|e| sevenz_rust::Error::other(e.to_string()))?;
+        // Rejected entries are not read, but the solid stream has to be consumed
+        std::io::copy(reader, &mut std::io::sink())?;
+        Ok(true)
+    })?;
+    Ok(())
+}
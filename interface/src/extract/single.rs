@@ -0,0 +1,14 @@
+use std::path::Path;
+
+use crate::{error::Result, utils::get_stem};
+
+use super::Unpacker;
+
+/// Decompress single gzip file. The output file name is `file_name` without `.gz`.
+pub(super) fn unpack_gz(file: &Path, file_name: &str, unpacker: &mut Unpacker) -> Result<()> {
+    let mut reader =
+        flate2::read::MultiGzDecoder::new(std::io::BufReader::new(std::fs::File::open(file)?));
+    let (stem, _) = get_stem(file_name);
+    unpacker.file(stem, &mut reader)?;
+    Ok(())
+}
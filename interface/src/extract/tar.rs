@@ -0,0 +1,52 @@
+use std::{io::Read, path::Path};
+
+use crate::error::Result;
+
+use super::{ArchiveFormat, Unpacker};
+
+pub(super) fn unpack(file: &Path, format: ArchiveFormat, unpacker: &mut Unpacker) -> Result<()> {
+    let file = std::io::BufReader::new(std::fs::File::open(file)?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::Tar => Box::new(file),
+        ArchiveFormat::TarGz => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        ArchiveFormat::TarXz => Box::new(lzma_rust2::XzReader::new(file, true)),
+        ArchiveFormat::TarBz2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        ArchiveFormat::TarZst => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(file)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+        ),
+        _ => unreachable!("not a tar archive"),
+    };
+
+    let mut archive = ::tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        let link_name = entry
+            .link_name_bytes()
+            .map(|t| String::from_utf8_lossy(&t).to_string())
+            .unwrap_or_default();
+
+        match entry.header().entry_type() {
+            ::tar::EntryType::Directory => unpacker.dir(&name)?,
+            ::tar::EntryType::Regular | ::tar::EntryType::Continuous => {
+                if let Some(_path) = unpacker.file(&name, &mut entry)? {
+                    #[cfg(unix)]
+                    if let Ok(mode) = entry.header().mode() {
+                        use std::os::unix::fs::PermissionsExt as _;
+                        std::fs::set_permissions(
+                            _path,
+                            std::fs::Permissions::from_mode(mode & 0o777),
+                        )?;
+                    }
+                }
+            }
+            ::tar::EntryType::Symlink => unpacker.symlink(&name, &link_name),
+            // Hard links point to an entry extracted before, relative to the archive root
+            ::tar::EntryType::Link => unpacker.hard_link(&name, &link_name)?,
+            // pax headers, device files etc.
+            _ => {}
+        }
+    }
+    Ok(())
+}
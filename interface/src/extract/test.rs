@@ -0,0 +1,926 @@
+use std::{
+    io::{Cursor, Write as _},
+    path::Path,
+};
+
+use super::{extract, ArchiveFormat};
+use crate::error::Error;
+
+/// Directories and files of the fixture archives
+const DIRS: &[&str] = &["app-1.0/", "app-1.0/docs/"];
+const FILES: &[(&str, &[u8])] = &[
+    ("app-1.0/app.exe", b"app"),
+    ("app-1.0/docs/readme.txt", b"readme"),
+];
+
+fn zip_fixture() -> Vec<u8> {
+    let mut writer = ::zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = ::zip::write::SimpleFileOptions::default();
+    for dir in DIRS {
+        writer.add_directory(*dir, options).unwrap();
+    }
+    for (name, data) in FILES {
+        writer.start_file(*name, options).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn sevenz_fixture() -> Vec<u8> {
+    let mut writer = sevenz_rust::SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+    for dir in DIRS {
+        let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+        entry.name = dir.trim_end_matches('/').to_string();
+        entry.is_directory = true;
+        writer.push_archive_entry::<&[u8]>(entry, None).unwrap();
+    }
+    for (name, data) in FILES {
+        let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+        entry.name = name.to_string();
+        entry.has_stream = true;
+        writer.push_archive_entry(entry, Some(*data)).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn tar_fixture() -> Vec<u8> {
+    let mut builder = ::tar::Builder::new(Vec::new());
+    for dir in DIRS {
+        let mut header = ::tar::Header::new_gnu();
+        header.set_entry_type(::tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        builder
+            .append_data(&mut header, dir, std::io::empty())
+            .unwrap();
+    }
+    for (name, data) in FILES {
+        let mut header = ::tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        builder.append_data(&mut header, name, *data).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+fn fixture(format: ArchiveFormat) -> Vec<u8> {
+    match format {
+        ArchiveFormat::Zip => zip_fixture(),
+        ArchiveFormat::SevenZip => sevenz_fixture(),
+        ArchiveFormat::Tar => tar_fixture(),
+        ArchiveFormat::TarGz => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&tar_fixture()).unwrap();
+            encoder.finish().unwrap()
+        }
+        ArchiveFormat::TarXz => {
+            let mut writer =
+                lzma_rust2::XzWriter::new(Vec::new(), lzma_rust2::XzOptions::default()).unwrap();
+            writer.write_all(&tar_fixture()).unwrap();
+            writer.finish().unwrap()
+        }
+        ArchiveFormat::TarBz2 => {
+            let mut encoder =
+                bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+            encoder.write_all(&tar_fixture()).unwrap();
+            encoder.finish().unwrap()
+        }
+        ArchiveFormat::TarZst => ruzstd::encoding::compress_to_vec(
+            &tar_fixture()[..],
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ),
+        ArchiveFormat::Gz => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(b"app").unwrap();
+            encoder.finish().unwrap()
+        }
+        // Installers have their own layout, see `mod msi` and `mod inno`
+        ArchiveFormat::Msi | ArchiveFormat::InnoSetup => unreachable!(),
+    }
+}
+
+const ARCHIVES: &[(&str, ArchiveFormat)] = &[
+    ("app.zip", ArchiveFormat::Zip),
+    ("app.7z", ArchiveFormat::SevenZip),
+    ("app.tar", ArchiveFormat::Tar),
+    ("app.tar.gz", ArchiveFormat::TarGz),
+    ("app.tar.xz", ArchiveFormat::TarXz),
+    ("app.tar.bz2", ArchiveFormat::TarBz2),
+    ("app.tar.zst", ArchiveFormat::TarZst),
+];
+
+/// Write the fixture to a file whose name doesn't tell the format, like a cached download
+fn write_fixture(dir: &Path, format: ArchiveFormat) -> std::path::PathBuf {
+    let path = dir.join("download");
+    std::fs::write(&path, fixture(format)).unwrap();
+    path
+}
+
+fn assert_file(path: &Path, content: &[u8]) {
+    assert_eq!(
+        std::fs::read(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e)),
+        content
+    );
+}
+
+#[test]
+fn detect_format() {
+    let cases = [
+        ("app.zip", Some(ArchiveFormat::Zip)),
+        ("APP.ZIP", Some(ArchiveFormat::Zip)),
+        ("app.nupkg", Some(ArchiveFormat::Zip)),
+        ("app.7z", Some(ArchiveFormat::SevenZip)),
+        ("app.tar", Some(ArchiveFormat::Tar)),
+        ("app.tar.gz", Some(ArchiveFormat::TarGz)),
+        ("app.tgz", Some(ArchiveFormat::TarGz)),
+        ("app.tar.xz", Some(ArchiveFormat::TarXz)),
+        ("app.txz", Some(ArchiveFormat::TarXz)),
+        ("app.tar.bz2", Some(ArchiveFormat::TarBz2)),
+        ("app.tbz2", Some(ArchiveFormat::TarBz2)),
+        ("app.tar.zst", Some(ArchiveFormat::TarZst)),
+        ("app.exe.gz", Some(ArchiveFormat::Gz)),
+        ("app.msi", Some(ArchiveFormat::Msi)),
+        ("app.exe", None),
+        ("zip", None),
+    ];
+    for (name, format) in cases {
+        assert_eq!(ArchiveFormat::detect(name), format, "{}", name);
+    }
+}
+
+#[test]
+fn extract_archives() {
+    for (name, format) in ARCHIVES {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = write_fixture(tmp.path(), *format);
+        let dest = tmp.path().join("dest");
+
+        extract(&file, name, &dest, None).unwrap_or_else(|e| panic!("{}: {}", name, e));
+
+        for (entry, content) in FILES {
+            assert_file(&dest.join(entry), content);
+        }
+    }
+}
+
+#[test]
+fn extract_archives_with_extract_dir() {
+    for (name, format) in ARCHIVES {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = write_fixture(tmp.path(), *format);
+        let dest = tmp.path().join("dest");
+
+        extract(&file, name, &dest, Some("app-1.0")).unwrap_or_else(|e| panic!("{}: {}", name, e));
+
+        assert_file(&dest.join("app.exe"), b"app");
+        assert_file(&dest.join("docs").join("readme.txt"), b"readme");
+        assert!(!dest.join("app-1.0").exists(), "{}", name);
+        assert!(!dest.join(super::STAGING_DIR).exists(), "{}", name);
+    }
+}
+
+#[test]
+fn extract_dir_with_backslashes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = write_fixture(tmp.path(), ArchiveFormat::Zip);
+    let dest = tmp.path().join("dest");
+
+    extract(&file, "app.zip", &dest, Some("app-1.0\\docs\\")).unwrap();
+
+    assert_file(&dest.join("readme.txt"), b"readme");
+}
+
+#[test]
+fn extract_to_existing_dir_merges() {
+    let tmp = tempfile::tempdir().unwrap();
+    let zip = write_fixture(tmp.path(), ArchiveFormat::Zip);
+    let dest = tmp.path().join("dest");
+    std::fs::create_dir_all(dest.join("docs")).unwrap();
+    std::fs::write(dest.join("docs").join("other.txt"), b"other").unwrap();
+
+    extract(&zip, "app.zip", &dest, Some("app-1.0")).unwrap();
+
+    assert_file(&dest.join("docs").join("readme.txt"), b"readme");
+    assert_file(&dest.join("docs").join("other.txt"), b"other");
+}
+
+#[test]
+fn missing_extract_dir() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = write_fixture(tmp.path(), ArchiveFormat::TarGz);
+    let dest = tmp.path().join("dest");
+
+    let err = extract(&file, "app.tar.gz", &dest, Some("app-2.0")).unwrap_err();
+
+    assert!(matches!(err, Error::ExtractDirNotFound(d) if d == "app-2.0"));
+    assert!(!dest.join(super::STAGING_DIR).exists());
+}
+
+#[test]
+fn decompress_single_gz() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = write_fixture(tmp.path(), ArchiveFormat::Gz);
+    let dest = tmp.path().join("dest");
+
+    extract(&file, "app.exe.gz", &dest, None).unwrap();
+
+    assert_file(&dest.join("app.exe"), b"app");
+}
+
+#[test]
+fn copy_non_archive() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("download");
+    std::fs::write(&file, b"installer").unwrap();
+    let dest = tmp.path().join("dest");
+
+    extract(&file, "setup.exe", &dest, None).unwrap();
+
+    assert_file(&dest.join("setup.exe"), b"installer");
+}
+
+mod msi {
+    use std::io::{Cursor, Write as _};
+
+    use ::msi::{Column, Insert, Package, PackageType, Value};
+
+    use super::{assert_file, extract};
+
+    /// (key, directory, `FileName` column, content)
+    const FILES: &[(&str, &str, &str, &[u8])] = &[
+        ("app_exe", "APPDIR", "app.exe", b"app"),
+        ("readme", "DOCS", "README~1.TXT|readme.txt", b"readme"),
+        ("license", "SAMEDIR", "LICENSE", b"license"),
+    ];
+
+    fn cabinet() -> Vec<u8> {
+        let mut builder = ::cab::CabinetBuilder::new();
+        let folder = builder.add_folder(::cab::CompressionType::MsZip);
+        for (key, ..) in FILES {
+            folder.add_file(*key);
+        }
+        let mut writer = builder.build(Cursor::new(Vec::new())).unwrap();
+        while let Some(mut file) = writer.next_file().unwrap() {
+            let (.., content) = FILES
+                .iter()
+                .find(|(key, ..)| *key == file.file_name())
+                .unwrap();
+            file.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Package with only the tables and columns used for extraction
+    fn package() -> Vec<u8> {
+        let mut package = Package::create(PackageType::Installer, Cursor::new(Vec::new())).unwrap();
+
+        package
+            .create_table(
+                "Directory",
+                vec![
+                    Column::build("Directory").primary_key().id_string(72),
+                    Column::build("Directory_Parent").nullable().id_string(72),
+                    Column::build("DefaultDir").string(255),
+                ],
+            )
+            .unwrap();
+        let directories = [
+            ("TARGETDIR", None, "SourceDir"),
+            ("ProgramFilesFolder", Some("TARGETDIR"), "PFiles"),
+            ("APPDIR", Some("ProgramFilesFolder"), "MYAPP|My App"),
+            ("DOCS", Some("APPDIR"), "docs:DOCS"),
+            ("SAMEDIR", Some("APPDIR"), "."),
+        ];
+        package
+            .insert_rows(
+                Insert::into("Directory").rows(
+                    directories
+                        .iter()
+                        .map(|(id, parent, name)| {
+                            vec![
+                                Value::from(*id),
+                                parent.map_or(Value::Null, Value::from),
+                                Value::from(*name),
+                            ]
+                        })
+                        .collect(),
+                ),
+            )
+            .unwrap();
+
+        package
+            .create_table(
+                "Component",
+                vec![
+                    Column::build("Component").primary_key().id_string(72),
+                    Column::build("Directory_").id_string(72),
+                ],
+            )
+            .unwrap();
+        package
+            .create_table(
+                "File",
+                vec![
+                    Column::build("File").primary_key().id_string(72),
+                    Column::build("Component_").id_string(72),
+                    Column::build("FileName").string(255),
+                    Column::build("Sequence").int16(),
+                ],
+            )
+            .unwrap();
+        for (i, (key, dir, name, _)) in FILES.iter().enumerate() {
+            let component = format!("{}_component", key);
+            package
+                .insert_rows(
+                    Insert::into("Component")
+                        .row(vec![Value::from(component.as_str()), Value::from(*dir)]),
+                )
+                .unwrap();
+            package
+                .insert_rows(Insert::into("File").row(vec![
+                    Value::from(*key),
+                    Value::from(component.as_str()),
+                    Value::from(*name),
+                    Value::Int(i as i32 + 1),
+                ]))
+                .unwrap();
+        }
+
+        package
+            .create_table(
+                "Media",
+                vec![
+                    Column::build("DiskId").primary_key().int16(),
+                    Column::build("LastSequence").int16(),
+                    Column::build("Cabinet").nullable().string(255),
+                ],
+            )
+            .unwrap();
+        package
+            .insert_rows(Insert::into("Media").row(vec![
+                Value::Int(1),
+                Value::Int(FILES.len() as i32),
+                Value::from("#app.cab"),
+            ]))
+            .unwrap();
+        package
+            .write_stream("app.cab")
+            .unwrap()
+            .write_all(&cabinet())
+            .unwrap();
+
+        package.into_inner().unwrap().into_inner()
+    }
+
+    #[test]
+    fn extract_msi() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("download");
+        std::fs::write(&file, package()).unwrap();
+        let dest = tmp.path().join("dest");
+
+        extract(&file, "app.msi", &dest, None).unwrap();
+
+        let app_dir = dest.join("PFiles").join("My App");
+        assert_file(&app_dir.join("app.exe"), b"app");
+        assert_file(&app_dir.join("docs").join("readme.txt"), b"readme");
+        assert_file(&app_dir.join("LICENSE"), b"license");
+    }
+
+    #[test]
+    fn extract_msi_with_extract_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("download");
+        std::fs::write(&file, package()).unwrap();
+        let dest = tmp.path().join("dest");
+
+        extract(&file, "app.msi", &dest, Some("PFiles\\My App")).unwrap();
+
+        assert_file(&dest.join("app.exe"), b"app");
+        assert_file(&dest.join("docs").join("readme.txt"), b"readme");
+        assert!(!dest.join("PFiles").exists());
+    }
+}
+
+/// InnoSetup installers are built by hand here, in the layout of InnoSetup 6.4.0 with
+/// uncompressed data and only the tables needed for files.
+mod inno {
+    use sha2::Digest as _;
+
+    use super::super::extract_as;
+    use super::{assert_file, ArchiveFormat};
+    use crate::error::Error;
+
+    const FILES: &[(&str, &[u8])] = &[
+        ("{app}\\app.exe", b"app"),
+        ("{app}\\docs\\readme.txt", b"readme"),
+        ("{tmp}\\setup.dll", b"setup"),
+    ];
+
+    /// Offset of the setup loader table, pointed to from `0x30`
+    const TABLE_OFFSET: u32 = 0x40;
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        let bytes = s
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect::<Vec<_>>();
+        out.extend((bytes.len() as u32).to_le_bytes());
+        out.extend(bytes);
+    }
+
+    /// Uncompressed header stream, in blocks with their CRC32
+    fn stream(data: &[u8]) -> Vec<u8> {
+        let mut blocks = Vec::new();
+        for block in data.chunks(4096) {
+            blocks.extend(crc32fast::hash(block).to_le_bytes());
+            blocks.extend(block);
+        }
+        let mut header = (blocks.len() as u32).to_le_bytes().to_vec();
+        header.push(0); // not compressed
+        let mut out = crc32fast::hash(&header).to_le_bytes().to_vec();
+        out.extend(header);
+        out.extend(blocks);
+        out
+    }
+
+    /// Header, then the file entries and wizard images
+    fn primary_header(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut h = Vec::new();
+        // App name, version, directories, license text, ...
+        for _ in 0..36 {
+            string(&mut h, "");
+        }
+        // Entry counts, only files and their locations
+        let count = files.len() as u32;
+        for n in [0, 0, 0, 0, 0, 0, 0, count, count, 0, 0, 0, 0, 0, 0, 0] {
+            h.extend(u32::to_le_bytes(n));
+        }
+        h.extend([0; 20]); // Windows version range
+        h.extend([0; 8]); // background colors
+        h.push(0); // wizard style
+        h.extend(100u32.to_le_bytes()); // wizard size percent
+        h.extend(100u32.to_le_bytes());
+        h.push(0); // image alpha format
+        h.extend([0; 48]); // encryption header
+        h.extend(0u64.to_le_bytes()); // extra disk space
+        h.extend(1u32.to_le_bytes()); // slices per disk
+        h.extend([0; 9]); // options, with stored compression
+        h.extend(0u64.to_le_bytes()); // uninstall display size
+        h.extend([0; 7]); // flags
+
+        for (i, (destination, _)) in files.iter().enumerate() {
+            string(&mut h, ""); // source
+            string(&mut h, destination);
+            // Font name, assembly name and conditions
+            for _ in 0..8 {
+                string(&mut h, "");
+            }
+            h.extend([0; 20]); // Windows version range
+            h.extend((i as u32).to_le_bytes()); // location
+            h.extend(0u32.to_le_bytes()); // attributes
+            h.extend(0u64.to_le_bytes()); // external size
+            h.extend((-1i16).to_le_bytes()); // permission
+            h.extend([0; 4]); // flags
+            h.push(0); // file type
+        }
+
+        // Wizard images
+        h.extend(0i32.to_le_bytes());
+        h.extend(0i32.to_le_bytes());
+        h
+    }
+
+    /// File locations in the secondary header stream, and the data chunks
+    fn locations_and_data(files: &[(&str, &[u8])]) -> (Vec<u8>, Vec<u8>) {
+        let mut locations = Vec::new();
+        let mut data = Vec::new();
+        for (_, content) in files {
+            locations.extend([0; 8]); // first and last slice
+            locations.extend((data.len() as u32).to_le_bytes()); // chunk offset
+            locations.extend(0u64.to_le_bytes()); // offset in the chunk
+            locations.extend((content.len() as u64).to_le_bytes());
+            locations.extend((content.len() as u64).to_le_bytes()); // chunk size
+            locations.extend(sha2::Sha256::digest(content));
+            locations.extend([0; 16]); // file time and version
+            locations.extend([0; 2]); // flags, not compressed
+            locations.push(0); // sign mode
+
+            data.extend(b"zlb\x1a");
+            data.extend(*content);
+        }
+        (locations, data)
+    }
+
+    fn installer(version: &str, files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut setup_data = vec![0; 64];
+        let version = format!("Inno Setup Setup Data ({})", version);
+        setup_data[..version.len()].copy_from_slice(version.as_bytes());
+        let (locations, data) = locations_and_data(files);
+        setup_data.extend(stream(&primary_header(files)));
+        setup_data.extend(stream(&locations));
+
+        // Setup loader table of 5.1.5 and later, revision 1
+        let header_offset = TABLE_OFFSET + 12 + 4 * 8;
+        let data_offset = header_offset + setup_data.len() as u32;
+        let mut table = b"rDlPtS\xCD\xE6\xD7{\x0B*".to_vec();
+        for n in [1, 0, 0, 0, 0, header_offset, data_offset] {
+            table.extend(u32::to_le_bytes(n));
+        }
+        table.extend(crc32fast::hash(&table).to_le_bytes());
+
+        let mut exe = vec![0; 0x30];
+        exe.extend(b"Inno");
+        exe.extend(TABLE_OFFSET.to_le_bytes());
+        exe.extend((!TABLE_OFFSET).to_le_bytes());
+        exe.resize(TABLE_OFFSET as usize, 0);
+        exe.extend(table);
+        exe.extend(setup_data);
+        exe.extend(data);
+        exe
+    }
+
+    fn extract_installer(
+        installer: Vec<u8>,
+        extract_dir: Option<&str>,
+    ) -> (tempfile::TempDir, crate::error::Result<()>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("download");
+        std::fs::write(&file, installer).unwrap();
+        let dest = tmp.path().join("dest");
+        let res = extract_as(
+            &file,
+            "setup.exe",
+            Some(ArchiveFormat::InnoSetup),
+            &dest,
+            extract_dir,
+        );
+        (tmp, res)
+    }
+
+    #[test]
+    fn extract_app_files() {
+        let (tmp, res) = extract_installer(installer("6.4.0", FILES), None);
+        res.unwrap();
+
+        let dest = tmp.path().join("dest");
+        assert_file(&dest.join("app.exe"), b"app");
+        assert_file(&dest.join("docs").join("readme.txt"), b"readme");
+        assert!(!dest.join("setup.dll").exists());
+        assert_eq!(std::fs::read_dir(&dest).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn extract_app_files_with_extract_dir() {
+        let (tmp, res) = extract_installer(installer("6.4.0", FILES), Some("docs"));
+        res.unwrap();
+
+        let dest = tmp.path().join("dest");
+        assert_file(&dest.join("readme.txt"), b"readme");
+        assert!(!dest.join("app.exe").exists());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let (_tmp, res) = extract_installer(installer("7.5.0", FILES), None);
+        let err = res.unwrap_err();
+        assert!(
+            matches!(&err, Error::UnsupportedInnoVersion(v) if v == "7.5.0"),
+            "{:?}",
+            err
+        );
+        assert!(err.to_string().contains("7.5.0"));
+    }
+
+    #[test]
+    fn unknown_version() {
+        let (_tmp, res) = extract_installer(installer("6.x", FILES), None);
+        assert!(matches!(res, Err(Error::UnsupportedInnoVersion(v)) if v == "6.x"));
+    }
+
+    #[test]
+    fn not_an_installer() {
+        let (_tmp, res) = extract_installer(b"MZ not an installer".to_vec(), None);
+        assert!(matches!(res, Err(Error::Inno(_))));
+    }
+
+    #[test]
+    fn app_files_escaping_app_dir() {
+        let files: &[(&str, &[u8])] = &[("{app}\\..\\evil.txt", b"evil")];
+        let (tmp, res) = extract_installer(installer("6.4.0", files), None);
+
+        assert!(matches!(res, Err(Error::UnsafeArchiveEntries(_))));
+        assert!(!tmp.path().join("evil.txt").exists());
+    }
+}
+
+/// Archives with entries that try to escape the destination
+mod malicious {
+    use std::{
+        io::{Cursor, Write as _},
+        path::Path,
+    };
+
+    use super::super::{extract, extract_to_path};
+    use crate::error::Error;
+
+    // Symlinks are only tested on Unix, because creating them needs privileges on Windows
+    #[cfg_attr(not(unix), allow(dead_code))]
+    enum Entry {
+        File(&'static str),
+        Symlink(&'static str, &'static str),
+        HardLink(&'static str, &'static str),
+    }
+
+    /// Entry kinds of zip, which has no hard links
+    #[cfg_attr(not(unix), allow(dead_code))]
+    enum ZipEntry {
+        File(&'static str),
+        Symlink(&'static str, &'static str),
+    }
+
+    fn zip(entries: &[ZipEntry]) -> Vec<u8> {
+        let mut writer = ::zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = ::zip::write::SimpleFileOptions::default();
+        for entry in entries {
+            match entry {
+                ZipEntry::File(name) => {
+                    writer.start_file(*name, options).unwrap();
+                    writer.write_all(b"evil").unwrap();
+                }
+                ZipEntry::Symlink(name, target) => {
+                    writer.add_symlink(*name, *target, options).unwrap()
+                }
+            }
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// `tar::Builder` refuses `..` in paths, so names are written to the header directly
+    fn tar(entries: &[Entry]) -> Vec<u8> {
+        fn header(name: &str, entry_type: ::tar::EntryType, size: u64) -> ::tar::Header {
+            let mut header = ::tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(entry_type);
+            header.set_size(size);
+            header.set_mode(0o644);
+            header
+        }
+
+        let mut builder = ::tar::Builder::new(Vec::new());
+        for entry in entries {
+            let (mut header, data): (_, &[u8]) = match entry {
+                Entry::File(name) => (header(name, ::tar::EntryType::Regular, 4), b"evil"),
+                Entry::Symlink(name, target) | Entry::HardLink(name, target) => {
+                    let entry_type = match entry {
+                        Entry::Symlink(..) => ::tar::EntryType::Symlink,
+                        _ => ::tar::EntryType::Link,
+                    };
+                    let mut header = header(name, entry_type, 0);
+                    header.as_gnu_mut().unwrap().linkname[..target.len()]
+                        .copy_from_slice(target.as_bytes());
+                    (header, &[])
+                }
+            };
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn sevenz(names: &[&str]) -> Vec<u8> {
+        let mut writer = sevenz_rust::SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+        for name in names {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            entry.has_stream = true;
+            writer
+                .push_archive_entry(entry, Some(&b"evil"[..]))
+                .unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Extract `archive` into `<tmp>/a/dest`, and return the rejected entries
+    fn extract_rejected(
+        tmp: &Path,
+        file_name: &str,
+        archive: Vec<u8>,
+        extract_dir: Option<&str>,
+    ) -> Vec<String> {
+        let file = tmp.join("download");
+        std::fs::write(&file, archive).unwrap();
+        let dest = tmp.join("a").join("dest");
+
+        match extract(&file, file_name, &dest, extract_dir) {
+            Err(Error::UnsafeArchiveEntries(rejected)) => rejected,
+            res => panic!("Expected unsafe entries, got {:?}", res),
+        }
+    }
+
+    /// Nothing but `dest` and the archive itself may exist in `tmp`
+    fn assert_nothing_escaped(tmp: &Path) {
+        let mut entries = walk(tmp);
+        entries.sort();
+        assert!(
+            entries
+                .iter()
+                .all(|p| p == "a" || p == "download" || p.starts_with("a/dest")),
+            "{:?}",
+            entries
+        );
+    }
+
+    fn walk(dir: &Path) -> Vec<String> {
+        fn walk_inner(root: &Path, dir: &Path, entries: &mut Vec<String>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                let relative = path.strip_prefix(root).unwrap();
+                entries.push(relative.to_string_lossy().replace('\\', "/"));
+                if path.is_dir() && !path.is_symlink() {
+                    walk_inner(root, &path, entries);
+                }
+            }
+        }
+        let mut entries = Vec::new();
+        walk_inner(dir, dir, &mut entries);
+        entries
+    }
+
+    const TRAVERSAL: &[&str] = &[
+        "../evil.txt",
+        "../../evil.txt",
+        "ok/../../evil.txt",
+        "..\\evil.txt",
+        "/evil.txt",
+        "\\evil.txt",
+        "C:\\evil.txt",
+    ];
+
+    #[test]
+    fn zip_path_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut entries = vec![ZipEntry::File("ok.txt")];
+        entries.extend(TRAVERSAL.iter().map(|name| ZipEntry::File(name)));
+
+        let rejected = extract_rejected(tmp.path(), "app.zip", zip(&entries), None);
+
+        assert_eq!(rejected, TRAVERSAL);
+        assert!(tmp.path().join("a/dest/ok.txt").is_file());
+        assert_nothing_escaped(tmp.path());
+    }
+
+    #[test]
+    fn tar_path_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut entries = vec![Entry::File("ok.txt")];
+        entries.extend(TRAVERSAL.iter().map(|name| Entry::File(name)));
+        entries.push(Entry::HardLink("passwd", "../../../etc/passwd"));
+
+        let rejected = extract_rejected(tmp.path(), "app.tar", tar(&entries), None);
+
+        let mut expected = TRAVERSAL.to_vec();
+        expected.push("passwd => ../../../etc/passwd");
+        assert_eq!(rejected, expected);
+        assert!(tmp.path().join("a/dest/ok.txt").is_file());
+        assert_nothing_escaped(tmp.path());
+    }
+
+    #[test]
+    fn sevenz_path_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut names = vec!["ok.txt"];
+        names.extend(TRAVERSAL);
+
+        let rejected = extract_rejected(tmp.path(), "app.7z", sevenz(&names), None);
+
+        assert_eq!(rejected, TRAVERSAL);
+        assert!(tmp.path().join("a/dest/ok.txt").is_file());
+        assert_nothing_escaped(tmp.path());
+    }
+
+    #[test]
+    fn extract_dir_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("download");
+        std::fs::write(&file, zip(&[ZipEntry::File("app/ok.txt")])).unwrap();
+
+        let err = extract(&file, "app.zip", &tmp.path().join("dest"), Some("../..")).unwrap_err();
+
+        assert!(matches!(err, Error::ExtractDirNotFound(_)));
+    }
+
+    #[test]
+    fn extract_to_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("app/1.0");
+
+        assert_eq!(
+            extract_to_path(&dir, "bin\\tools").unwrap(),
+            dir.join("bin").join("tools")
+        );
+        assert_eq!(extract_to_path(&dir, "").unwrap(), dir);
+        for extract_to in ["..\\..\\x", "../x", "bin/../../x", "/tmp/x", "C:\\x"] {
+            let err = extract_to_path(&dir, extract_to).unwrap_err();
+            assert!(matches!(err, Error::UnsafeExtractTo(_)), "{}", extract_to);
+        }
+    }
+
+    #[cfg(unix)]
+    mod symlink {
+        use super::*;
+
+        #[test]
+        fn zip_symlinks() {
+            let tmp = tempfile::tempdir().unwrap();
+            let entries = [
+                ZipEntry::File("dir/ok.txt"),
+                ZipEntry::Symlink("inside", "dir/ok.txt"),
+                ZipEntry::Symlink("dir/up", "../dir/ok.txt"),
+                ZipEntry::Symlink("parent", ".."),
+                ZipEntry::Symlink("absolute", "/etc/passwd"),
+                ZipEntry::Symlink("dir/escape", "../../dest2"),
+            ];
+
+            let rejected = extract_rejected(tmp.path(), "app.zip", zip(&entries), None);
+
+            assert_eq!(
+                rejected,
+                [
+                    "parent -> ..",
+                    "absolute -> /etc/passwd",
+                    "dir/escape -> ../../dest2"
+                ]
+            );
+            let dest = tmp.path().join("a/dest");
+            assert_eq!(std::fs::read(dest.join("inside")).unwrap(), b"evil");
+            assert_eq!(std::fs::read(dest.join("dir/up")).unwrap(), b"evil");
+            assert!(!dest.join("parent").is_symlink());
+            assert!(!dest.join("dir/escape").is_symlink());
+        }
+
+        /// Each symlink looks fine on its own, but `self/..` is the parent of `dest`
+        #[test]
+        fn tar_symlink_chain() {
+            let tmp = tempfile::tempdir().unwrap();
+            let entries = [
+                Entry::Symlink("escape", "self/.."),
+                Entry::Symlink("self", "."),
+            ];
+
+            let rejected = extract_rejected(tmp.path(), "app.tar", tar(&entries), None);
+
+            assert_eq!(rejected, ["escape -> self/.."]);
+            assert!(!tmp.path().join("a/dest/escape").is_symlink());
+            assert!(tmp.path().join("a/dest/self").is_symlink());
+            assert_nothing_escaped(tmp.path());
+        }
+
+        /// `a/pwned` would be created through `a`, in the parent of the parent of `dest`
+        #[test]
+        fn tar_symlink_through_symlink() {
+            let tmp = tempfile::tempdir().unwrap();
+            let entries = [Entry::Symlink("a", "../.."), Entry::Symlink("a/pwned", "x")];
+
+            let rejected = extract_rejected(tmp.path(), "app.tar", tar(&entries), None);
+
+            assert_eq!(rejected, ["a -> ../.."]);
+            assert!(!tmp.path().join("pwned").is_symlink());
+            assert_nothing_escaped(tmp.path());
+        }
+
+        /// Even a symlink inside `dest` can't be the parent of another one
+        #[test]
+        fn symlink_in_parent_path() {
+            let tmp = tempfile::tempdir().unwrap();
+            let entries = [
+                Entry::File("dir/ok.txt"),
+                Entry::Symlink("link", "dir"),
+                Entry::Symlink("link/nested", "ok.txt"),
+            ];
+
+            let rejected = extract_rejected(tmp.path(), "app.tar", tar(&entries), None);
+
+            assert_eq!(rejected, ["link/nested -> ok.txt"]);
+            assert!(!tmp.path().join("a/dest/dir/nested").exists());
+        }
+
+        /// Only `extract_dir` is kept, so symlinks must not point out of it
+        #[test]
+        fn symlink_outside_extract_dir() {
+            let tmp = tempfile::tempdir().unwrap();
+            let entries = [
+                Entry::File("other.txt"),
+                Entry::File("app/ok.txt"),
+                Entry::Symlink("app/inside", "ok.txt"),
+                Entry::Symlink("app/outside", "../other.txt"),
+            ];
+
+            let rejected = extract_rejected(tmp.path(), "app.tar", tar(&entries), Some("app"));
+
+            assert_eq!(rejected, ["app/outside -> ../other.txt"]);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::{io::Read as _, path::Path};
+
+use crate::error::Result;
+
+use super::Unpacker;
+
+pub(super) fn unpack(file: &Path, unpacker: &mut Unpacker) -> Result<()> {
+    let mut archive = ::zip::ZipArchive::new(std::fs::File::open(file)?)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+
+        if entry.is_dir() {
+            unpacker.dir(&name)?;
+        } else if entry.is_symlink() {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            unpacker.symlink(&name, &target);
+        } else if let Some(_path) = unpacker.file(&name, &mut entry)? {
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::os::unix::fs::PermissionsExt as _;
+                std::fs::set_permissions(_path, std::fs::Permissions::from_mode(mode & 0o777))?;
+            }
+        }
+    }
+    Ok(())
+}
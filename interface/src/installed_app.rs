@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,9 +8,24 @@ use crate::Context as _;
 
 use super::{bucket::Bucket, manifest::Manifest};
 
+#[cfg(test)]
+mod test;
+
+/// Link to the current version in the app directory
+const CURRENT: &str = "current";
+/// The new link while it is created, before it replaces [`CURRENT`]
+const CURRENT_TMP: &str = ".current.tmp";
+/// The old link while it is replaced on Windows
+#[cfg(windows)]
+const CURRENT_OLD: &str = ".current.old";
+
 pub async fn installed_apps() -> Result<Vec<InstalledApp>> {
     let mut apps = Vec::new();
-    let mut readdir = tokio::fs::read_dir(INSTALL_DIR.clone().join("apps")).await?;
+    let path = INSTALL_DIR.clone().join("apps");
+    if !path.exists() {
+        return Ok(apps);
+    }
+    let mut readdir = tokio::fs::read_dir(path).await?;
     while let Ok(Some(entry)) = readdir.next_entry().await {
         if let Some(name) = entry.file_name().to_str() {
             apps.push(InstalledApp::from_name(name));
@@ -39,20 +54,17 @@ impl InstalledApp {
     }
 
     pub async fn is_installed(&self) -> bool {
-        let mut path = INSTALL_DIR.clone();
-        path.push(&self.name);
-        path.exists()
+        self.path().exists()
     }
 
-    pub async fn versions(&self) -> Result<Vec<AppVersion>> {
-        let mut path = INSTALL_DIR.clone();
-        path.push(&self.name);
+    pub async fn versions(&self) -> Result<Vec<AppVersion<'_>>> {
         let mut versions = Vec::new();
-        let mut readdir = tokio::fs::read_dir(path).await?;
+        let mut readdir = tokio::fs::read_dir(self.path()).await?;
         while let Ok(Some(entry)) = readdir.next_entry().await {
             if let Some(version) = entry.file_name().to_str() {
-                // `current` is a symlink to the current version of the app
-                if version == "current" {
+                // `current` is a link to the current version of the app, and hidden entries are
+                // temporary links while it is switched
+                if version == CURRENT || version.starts_with('.') {
                     continue;
                 }
                 versions.push(AppVersion {
@@ -64,8 +76,8 @@ impl InstalledApp {
         Ok(versions)
     }
 
-    pub async fn current_version(&self) -> Result<AppVersion> {
-        let path = self.path().join("current");
+    pub async fn current_version(&self) -> Result<AppVersion<'_>> {
+        let path = self.path().join(CURRENT);
         if !path.exists() {
             return Err(crate::error::Error::InvalidState(
                 "No current version".to_string(),
@@ -80,12 +92,95 @@ impl InstalledApp {
             .to_string();
         Ok(AppVersion { app: self, version })
     }
+
+    /// Point `current` to the installed `version`. On Unix, the link is replaced in one step, so
+    /// `current` always points to either the old or the new version. On Windows, `current` is
+    /// missing for a moment while the link is replaced.
+    pub async fn link_current(&self, version: &str) -> Result<()> {
+        let target = self.path().join(version);
+        if !target.is_dir() {
+            return Err(Error::InvalidState(format!(
+                "Version {} of {} is not installed",
+                version, self.name
+            )));
+        }
+        replace_dir_link(&self.path().join(CURRENT), &target)
+            .await
+            .with_context(|| format!("Failed to link {} to {}", CURRENT, version))
+    }
+}
+
+/// Create or replace the directory link `link` to point to `target`. The new link is created
+/// next to it and renamed over it. This is a symlink on Unix and a junction on Windows, which
+/// doesn't need admin rights.
+async fn replace_dir_link(link: &Path, target: &Path) -> Result<()> {
+    let tmp = link.with_file_name(CURRENT_TMP);
+    remove_dir_link(&tmp).await?;
+    create_dir_link(target, &tmp)?;
+    if let Err(e) = rename_link(&tmp, link).await {
+        remove_dir_link(&tmp).await?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Create the directory link `link` to `target`: a symlink on Unix and a junction on Windows
+#[cfg(not(windows))]
+pub fn create_dir_link(target: &Path, link: &Path) -> Result<()> {
+    Ok(std::os::unix::fs::symlink(target, link)?)
+}
+
+#[cfg(windows)]
+pub fn create_dir_link(target: &Path, link: &Path) -> Result<()> {
+    Ok(junction::create(target, link)?)
+}
+
+/// rename(2) replaces an existing link atomically
+#[cfg(not(windows))]
+async fn rename_link(from: &Path, to: &Path) -> Result<()> {
+    Ok(tokio::fs::rename(from, to).await?)
+}
+
+/// Junctions are directories, which can't be replaced by a rename on Windows. The old link is
+/// moved aside first, and moved back if the new one can't take its place. Between the two
+/// renames `to` doesn't exist, so apps started through it in that window fail to start.
+#[cfg(windows)]
+async fn rename_link(from: &Path, to: &Path) -> Result<()> {
+    let old = to.with_file_name(CURRENT_OLD);
+    remove_dir_link(&old).await?;
+    let replaced = match tokio::fs::rename(to, &old).await {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(e.into()),
+    };
+    if let Err(e) = tokio::fs::rename(from, to).await {
+        if replaced {
+            tokio::fs::rename(&old, to).await?;
+        }
+        return Err(e.into());
+    }
+    remove_dir_link(&old).await
+}
+
+/// Remove a link made by [`create_dir_link`] if it exists, without touching its target
+pub async fn remove_dir_link(link: &Path) -> Result<()> {
+    #[cfg(windows)]
+    let res = tokio::fs::remove_dir(link).await;
+    #[cfg(not(windows))]
+    let res = tokio::fs::remove_file(link).await;
+    match res {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct AppInstallInfo {
     pub bucket: Bucket,
     pub architecture: String,
+    /// Names of the shims created for `bin`, so that uninstall removes exactly them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shims: Vec<String>,
 }
 
 /// Structure that represent one installed app version
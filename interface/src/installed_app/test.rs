@@ -0,0 +1,39 @@
+use super::{create_dir_link, replace_dir_link, CURRENT_TMP};
+
+#[tokio::test]
+async fn switch_link_between_versions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (v1, v2) = (tmp.path().join("1.0"), tmp.path().join("2.0"));
+    std::fs::create_dir(&v1).unwrap();
+    std::fs::create_dir(&v2).unwrap();
+    std::fs::write(v2.join("app.exe"), b"2.0").unwrap();
+    let current = tmp.path().join("current");
+
+    replace_dir_link(&current, &v1).await.unwrap();
+    assert_eq!(std::fs::read_link(&current).unwrap(), v1);
+
+    replace_dir_link(&current, &v2).await.unwrap();
+    assert_eq!(std::fs::read_link(&current).unwrap(), v2);
+    assert_eq!(std::fs::read(current.join("app.exe")).unwrap(), b"2.0");
+    assert!(!tmp.path().join(CURRENT_TMP).exists());
+
+    // Back to the old version, without touching the contents of the other one
+    replace_dir_link(&current, &v1).await.unwrap();
+    assert_eq!(std::fs::read_link(&current).unwrap(), v1);
+    assert!(v2.join("app.exe").exists());
+}
+
+#[tokio::test]
+async fn stale_temporary_link_is_replaced() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old, v1) = (tmp.path().join("0.9"), tmp.path().join("1.0"));
+    std::fs::create_dir(&old).unwrap();
+    std::fs::create_dir(&v1).unwrap();
+    // Left over by an interrupted switch
+    create_dir_link(&old, &tmp.path().join(CURRENT_TMP)).unwrap();
+
+    let current = tmp.path().join("current");
+    replace_dir_link(&current, &v1).await.unwrap();
+    assert_eq!(std::fs::read_link(&current).unwrap(), v1);
+    assert!(old.exists());
+}
@@ -1,9 +1,15 @@
 pub mod bucket;
 pub mod bucket_app;
+pub mod cache;
+pub mod config;
 pub mod dir;
 pub mod error;
+pub mod extract;
 pub mod installed_app;
+pub mod lnk;
 pub mod manifest;
+pub mod shim;
 mod utils;
+pub mod version;
 
 use error::*;
@@ -0,0 +1,196 @@
+//! Writer of Shell Link (`.lnk`) files, the shortcuts of Windows, as specified in
+//! [MS-SHLLINK](https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink/).
+//!
+//! The target is stored both in `LinkInfo` and in an `EnvironmentVariableDataBlock`, which
+//! Windows resolves without an ID list of shell items.
+
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+const HEADER_SIZE: u32 = 0x4C;
+/// `00021401-0000-0000-C000-000000000046`
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_INFO: u32 = 0x2;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+const HAS_EXP_STRING: u32 = 0x200;
+
+const SW_SHOWNORMAL: u32 = 1;
+
+/// Size of `LinkInfoHeader` with the offsets of the Unicode strings
+const LINK_INFO_HEADER_SIZE: u32 = 0x24;
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const DRIVE_FIXED: u32 = 3;
+/// `VolumeID` with an empty label
+const VOLUME_ID_SIZE: u32 = 0x11;
+
+const ENVIRONMENT_VARIABLE_BLOCK_SIZE: u32 = 0x314;
+const ENVIRONMENT_VARIABLE_BLOCK_SIGNATURE: u32 = 0xA000_0001;
+/// Length of the target fields in `EnvironmentVariableDataBlock`, including the terminating null
+const MAX_PATH: usize = 260;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellLink {
+    /// Absolute path of the file the shortcut runs
+    pub target: PathBuf,
+    pub arguments: Option<String>,
+    pub working_dir: Option<PathBuf>,
+    /// File with the icon, the first icon of the target if not set
+    pub icon: Option<PathBuf>,
+}
+
+impl ShellLink {
+    pub fn new(target: &Path) -> Self {
+        Self {
+            target: target.to_path_buf(),
+            arguments: None,
+            working_dir: None,
+            icon: None,
+        }
+    }
+
+    /// Contents of the `.lnk` file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let target = self.target.to_string_lossy();
+        let working_dir = self.working_dir.as_ref().map(|p| p.to_string_lossy());
+        let icon = self.icon.as_ref().map(|p| p.to_string_lossy());
+        // Longer targets fit only in `LinkInfo`
+        let exp_string = target.encode_utf16().count() < MAX_PATH;
+
+        let mut flags = HAS_LINK_INFO | IS_UNICODE;
+        for (is_set, flag) in [
+            (working_dir.is_some(), HAS_WORKING_DIR),
+            (self.arguments.is_some(), HAS_ARGUMENTS),
+            (icon.is_some(), HAS_ICON_LOCATION),
+            (exp_string, HAS_EXP_STRING),
+        ] {
+            if is_set {
+                flags |= flag;
+            }
+        }
+
+        let mut bytes = Vec::new();
+        put_u32(&mut bytes, HEADER_SIZE);
+        bytes.extend_from_slice(&LINK_CLSID);
+        put_u32(&mut bytes, flags);
+        // FileAttributes, CreationTime, AccessTime, WriteTime and FileSize are unknown
+        bytes.extend_from_slice(&[0; 4 + 8 * 3 + 4]);
+        // IconIndex
+        put_u32(&mut bytes, 0);
+        put_u32(&mut bytes, SW_SHOWNORMAL);
+        // HotKey and the reserved fields
+        bytes.extend_from_slice(&[0; 2 + 2 + 4 + 4]);
+
+        bytes.extend_from_slice(&link_info(&target));
+
+        // StringData, in the order of the spec
+        for value in [
+            working_dir.as_deref(),
+            self.arguments.as_deref(),
+            icon.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let utf16 = value
+                .encode_utf16()
+                .take(u16::MAX.into())
+                .collect::<Vec<_>>();
+            put_u16(&mut bytes, utf16.len() as u16);
+            utf16.iter().for_each(|c| put_u16(&mut bytes, *c));
+        }
+
+        if exp_string {
+            put_u32(&mut bytes, ENVIRONMENT_VARIABLE_BLOCK_SIZE);
+            put_u32(&mut bytes, ENVIRONMENT_VARIABLE_BLOCK_SIGNATURE);
+            bytes.extend_from_slice(&fixed_ansi(&target));
+            fixed_utf16(&target)
+                .iter()
+                .for_each(|c| put_u16(&mut bytes, *c));
+        }
+        // TerminalBlock
+        put_u32(&mut bytes, 0);
+        bytes
+    }
+}
+
+/// `LinkInfo` of a file on a fixed drive
+fn link_info(target: &str) -> Vec<u8> {
+    let ansi = ansi(target);
+    let utf16 = target.encode_utf16().collect::<Vec<_>>();
+
+    let volume_id_offset = LINK_INFO_HEADER_SIZE;
+    let local_base_path_offset = volume_id_offset + VOLUME_ID_SIZE;
+    let common_path_suffix_offset = local_base_path_offset + ansi.len() as u32 + 1;
+    let local_base_path_offset_unicode = common_path_suffix_offset + 1;
+    let common_path_suffix_offset_unicode =
+        local_base_path_offset_unicode + (utf16.len() as u32 + 1) * 2;
+    let size = common_path_suffix_offset_unicode + 2;
+
+    let mut bytes = Vec::with_capacity(size as usize);
+    put_u32(&mut bytes, size);
+    put_u32(&mut bytes, LINK_INFO_HEADER_SIZE);
+    put_u32(&mut bytes, VOLUME_ID_AND_LOCAL_BASE_PATH);
+    put_u32(&mut bytes, volume_id_offset);
+    put_u32(&mut bytes, local_base_path_offset);
+    // CommonNetworkRelativeLinkOffset
+    put_u32(&mut bytes, 0);
+    put_u32(&mut bytes, common_path_suffix_offset);
+    put_u32(&mut bytes, local_base_path_offset_unicode);
+    put_u32(&mut bytes, common_path_suffix_offset_unicode);
+
+    // VolumeID: size, drive type, serial number, label offset and the empty label
+    put_u32(&mut bytes, VOLUME_ID_SIZE);
+    put_u32(&mut bytes, DRIVE_FIXED);
+    put_u32(&mut bytes, 0);
+    put_u32(&mut bytes, 0x10);
+    bytes.push(0);
+
+    // LocalBasePath is the whole target, so CommonPathSuffix is empty
+    bytes.extend_from_slice(&ansi);
+    bytes.push(0);
+    bytes.push(0);
+    utf16.iter().for_each(|c| put_u16(&mut bytes, *c));
+    put_u16(&mut bytes, 0);
+    put_u16(&mut bytes, 0);
+    bytes
+}
+
+/// `s` in the system code page, which is only known on the machine, so characters other than
+/// ASCII become `?`. Windows uses the Unicode strings next to these.
+fn ansi(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .collect()
+}
+
+fn fixed_ansi(s: &str) -> [u8; MAX_PATH] {
+    let mut fixed = [0; MAX_PATH];
+    for (dst, src) in fixed.iter_mut().zip(ansi(s).into_iter().take(MAX_PATH - 1)) {
+        *dst = src;
+    }
+    fixed
+}
+
+fn fixed_utf16(s: &str) -> [u16; MAX_PATH] {
+    let mut fixed = [0; MAX_PATH];
+    for (dst, src) in fixed.iter_mut().zip(s.encode_utf16().take(MAX_PATH - 1)) {
+        *dst = src;
+    }
+    fixed
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use lnk::{extradata::ExtraDataBlock, linkinfo::DriveType, LinkFlags};
+
+use super::ShellLink;
+
+/// Parse `link` with the `lnk` crate, which is written from MS-SHLLINK independently of
+/// [`ShellLink`]. Returns the link and the target in `EnvironmentVariableDataBlock`.
+fn parse(link: &ShellLink) -> (ShellLink, Option<String>) {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.lnk");
+    std::fs::write(&path, link.to_bytes()).unwrap();
+    let parsed = lnk::ShellLink::open(&path, lnk::encoding::WINDOWS_1252).unwrap();
+
+    let flags = parsed.header().link_flags();
+    assert!(flags.contains(LinkFlags::IS_UNICODE));
+    assert!(!flags.contains(LinkFlags::HAS_LINK_TARGET_ID_LIST));
+    assert!(parsed.linktarget_id_list().is_none());
+
+    let info = parsed.link_info().as_ref().unwrap();
+    let volume_id = info.volume_id().as_ref().unwrap();
+    assert!(matches!(volume_id.drive_type(), DriveType::DriveFixed));
+    assert_eq!(volume_id.volume_label(), "");
+    // The whole target is in LocalBasePath, in both ANSI and Unicode
+    let target = link.target.to_str().unwrap();
+    let ansi = target
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '?' })
+        .collect::<String>();
+    assert_eq!(info.local_base_path(), Some(ansi.as_str()));
+    assert_eq!(info.common_path_suffix(), "");
+    assert_eq!(info.local_base_path_unicode().as_deref(), Some(target));
+
+    let exp_target = parsed
+        .extra_data()
+        .blocks()
+        .iter()
+        .find_map(|block| match block {
+            ExtraDataBlock::EnvironmentProps(block) => {
+                assert_eq!(block.target_ansi(), &ansi);
+                // `lnk` cuts the fixed size string at the byte index of the null as a char
+                // index, which keeps a null for each character other than ASCII
+                block
+                    .target_unicode()
+                    .as_ref()
+                    .map(|t| t.trim_end_matches('\0').to_string())
+            }
+            _ => None,
+        });
+    assert_eq!(
+        flags.contains(LinkFlags::HAS_EXP_STRING),
+        exp_target.is_some()
+    );
+
+    let strings = parsed.string_data();
+    let link = ShellLink {
+        target: PathBuf::from(parsed.link_target().unwrap()),
+        arguments: strings.command_line_arguments().clone(),
+        working_dir: strings.working_dir().as_ref().map(PathBuf::from),
+        icon: strings.icon_location().as_ref().map(PathBuf::from),
+    };
+    (link, exp_target)
+}
+
+#[test]
+fn round_trip() {
+    let link = ShellLink {
+        target: PathBuf::from(r"C:\Users\me\scoop\apps\vscode\current\Code.exe"),
+        arguments: Some(r#"--user-data-dir "C:\Users\me\scoop\persist\vscode\data""#.to_string()),
+        working_dir: Some(PathBuf::from(r"C:\Users\me\scoop\apps\vscode\current")),
+        icon: Some(PathBuf::from(
+            r"C:\Users\me\scoop\apps\vscode\current\resources\app.ico",
+        )),
+    };
+    let (parsed, exp_target) = parse(&link);
+    assert_eq!(parsed, link);
+    assert_eq!(
+        exp_target.as_deref(),
+        Some(r"C:\Users\me\scoop\apps\vscode\current\Code.exe")
+    );
+
+    let link = ShellLink::new(Path::new(r"C:\Users\José\scoop\apps\app\current\app.exe"));
+    let (parsed, exp_target) = parse(&link);
+    assert_eq!(parsed, link);
+    assert_eq!(exp_target.as_deref(), link.target.to_str());
+}
+
+#[test]
+fn long_target_is_only_in_link_info() {
+    let target = format!(r"C:\{}\app.exe", "a".repeat(300));
+    let link = ShellLink::new(Path::new(&target));
+    let (parsed, exp_target) = parse(&link);
+    assert_eq!(parsed.target, PathBuf::from(target));
+    assert_eq!(exp_target, None);
+}
@@ -11,6 +11,24 @@ impl Manifest {
         let content = tokio::fs::read_to_string(path).await?;
         Manifest::from_str(&content).with_context(|| format!("In file `{}`", path.display()))
     }
+
+    /// Value of the `Cookie` header for downloads, made from `cookie`
+    pub fn cookie_header(&self) -> Option<String> {
+        let cookie = self.cookie.as_ref().filter(|c| !c.is_empty())?;
+        let mut pairs = cookie
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(v) => v.to_string(),
+                    None => String::new(),
+                };
+                format!("{}={}", name, value)
+            })
+            .collect::<Vec<_>>();
+        pairs.sort();
+        Some(pairs.join("; "))
+    }
 }
 
 impl FromStr for Manifest {
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 pub mod arch;
 mod bin;
 mod download_url;
+mod hash;
 mod persist;
 #[cfg(test)]
 mod test;
@@ -12,12 +13,12 @@ mod test;
 pub use bin::Bin;
 use bin::{parse_bin, serialize_bin};
 pub use download_url::DownloadUrl;
+pub use hash::{HashAlgorithm, ManifestHash};
+pub use persist::Persist;
 use persist::{parse_persist, serialize_persist};
 
 use crate::bucket_app::BucketAppName;
 
-use self::persist::Persist;
-
 #[serde_as]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Manifest {
@@ -49,7 +50,8 @@ pub struct Manifest {
     pub extract_dir: Option<Vec<String>>,
     #[serde_as(deserialize_as = "Option<OneOrMany<_, PreferOne>>")]
     pub extract_to: Option<Vec<String>>,
-    pub hash: Option<Hash>,
+    #[serde_as(as = "Option<OneOrMany<_, PreferOne>>")]
+    pub hash: Option<Vec<ManifestHash>>,
     pub homepage: String,
     /// True if the installer InnoSetup based. Found in
     /// https://github.com/ScoopInstaller/Main/search?l=JSON&q=innosetup
@@ -113,7 +115,8 @@ pub struct ArchManifest {
     pub env_set: Option<HashMap<String, Option<serde_json::Value>>>,
     #[serde_as(deserialize_as = "Option<OneOrMany<_, PreferOne>>")]
     pub extract_dir: Option<Vec<String>>,
-    pub hash: Option<Hash>,
+    #[serde_as(as = "Option<OneOrMany<_, PreferOne>>")]
+    pub hash: Option<Vec<ManifestHash>>,
     pub installer: Option<ManifestInstaller>,
     /// Deprecated
     #[serde_as(deserialize_as = "Option<OneOrMany<_, PreferOne>>")]
@@ -187,13 +190,6 @@ pub struct SourceforgeDetail {
     pub project: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(untagged)]
-pub enum Hash {
-    String(String),
-    StringArray(Vec<String>),
-}
-
 #[serde_as]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ManifestInstaller {
@@ -1,4 +1,6 @@
-use super::{ArchManifest, Manifest};
+use crate::error::{Error, Result};
+
+use super::{ArchManifest, DownloadUrl, Manifest, ManifestHash};
 
 macro_rules! merge {
     ($m:ident, $arch:ident, $prop:ident) => {
@@ -16,6 +18,23 @@ pub enum Architecture {
     None,
 }
 
+impl Architecture {
+    /// Architecture of the running machine
+    pub fn current() -> Self {
+        ARCH
+    }
+
+    /// Name used in manifests and `install.json`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Architecture::X86 => "32bit",
+            Architecture::Amd64 => "64bit",
+            Architecture::Arm64 => "arm64",
+            Architecture::None => "none",
+        }
+    }
+}
+
 static ARCH: Architecture = {
     #[cfg(target_arch = "x86")]
     {
@@ -78,3 +97,23 @@ impl Manifest {
         self.architecture(ARCH)
     }
 }
+
+impl ArchManifest {
+    /// Pair each url with the hash at the same index.
+    /// All hashes are `None` if there is no `hash` field, and it is an error if the number of
+    /// hashes doesn't match the number of urls.
+    pub fn url_hashes(&self) -> Result<Vec<(DownloadUrl, Option<ManifestHash>)>> {
+        let urls = self.url.clone().unwrap_or_default();
+        match &self.hash {
+            None => Ok(urls.into_iter().map(|url| (url, None)).collect()),
+            Some(hashes) if hashes.len() == urls.len() => Ok(urls
+                .into_iter()
+                .zip(hashes.iter().cloned().map(Some))
+                .collect()),
+            Some(hashes) => Err(Error::HashCountMismatch {
+                urls: urls.len(),
+                hashes: hashes.len(),
+            }),
+        }
+    }
+}
@@ -36,6 +36,14 @@ where
             formatter.write_str("string or list of strings")
         }
 
+        // `null` is written by the serializer when there is no field
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
+
         fn visit_str<E>(self, target: &str) -> Result<Self::Value, E>
         where
             E: de::Error,
@@ -13,6 +13,22 @@ pub struct DownloadUrl {
     pub file_name: Option<String>,
 }
 
+impl DownloadUrl {
+    /// Name of the downloaded file. This is the `#/` rename if specified,
+    /// otherwise the last path segment of the url.
+    pub fn target_name(&self) -> String {
+        if let Some(file_name) = &self.file_name {
+            return file_name.clone();
+        }
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        path.trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
 impl FromStr for DownloadUrl {
     type Err = Infallible;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -33,7 +49,7 @@ impl FromStr for DownloadUrl {
 impl Display for DownloadUrl {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         if let Some(file_name) = &self.file_name {
-            write!(f, "{}/#{}", self.url, file_name)
+            write!(f, "{}#/{}", self.url, file_name)
         } else {
             write!(f, "{}", self.url)
         }
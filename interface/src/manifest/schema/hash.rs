@@ -0,0 +1,124 @@
+use core::fmt;
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Length of the digest in bytes
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 16,
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(Error::InvalidHash(format!("Unsupported algorithm `{}`", s))),
+        }
+    }
+}
+
+/// Hash of a downloaded file.
+/// Represented as `algorithm:hex` in manifests. Hashes without `algorithm:` are sha256.
+#[derive(DeserializeFromStr, SerializeDisplay, Debug, Clone)]
+pub struct ManifestHash {
+    pub algorithm: HashAlgorithm,
+    pub digest: Vec<u8>,
+    /// Text the hash was parsed from. It is written back as is, so that a saved manifest keeps
+    /// the prefix and case of the one in the bucket.
+    text: Option<String>,
+}
+
+impl ManifestHash {
+    pub fn new(algorithm: HashAlgorithm, digest: Vec<u8>) -> Self {
+        Self {
+            algorithm,
+            digest,
+            text: None,
+        }
+    }
+
+    /// Digest as lowercase hex string
+    pub fn hex(&self) -> String {
+        hex::encode(&self.digest)
+    }
+}
+
+impl FromStr for ManifestHash {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, digest) = match s.split_once(':') {
+            Some((algorithm, digest)) => (algorithm.parse()?, digest),
+            None => (HashAlgorithm::Sha256, s),
+        };
+        let digest = hex::decode(digest)
+            .map_err(|e| Error::InvalidHash(format!("`{}` is not valid hex: {}", digest, e)))?;
+        if digest.len() != algorithm.digest_len() {
+            return Err(Error::InvalidHash(format!(
+                "{} digest must be {} bytes, but `{}` is {} bytes",
+                algorithm.as_str(),
+                algorithm.digest_len(),
+                s,
+                digest.len()
+            )));
+        }
+        Ok(ManifestHash {
+            algorithm,
+            digest,
+            text: Some(s.to_string()),
+        })
+    }
+}
+
+/// Hashes are equal if their digests are, however they are written
+impl PartialEq for ManifestHash {
+    fn eq(&self, other: &Self) -> bool {
+        self.algorithm == other.algorithm && self.digest == other.digest
+    }
+}
+
+impl Eq for ManifestHash {}
+
+impl Display for ManifestHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(text) = &self.text {
+            return f.write_str(text);
+        }
+        match self.algorithm {
+            HashAlgorithm::Sha256 => write!(f, "{}", self.hex()),
+            algorithm => write!(f, "{}:{}", algorithm.as_str(), self.hex()),
+        }
+    }
+}
@@ -2,9 +2,9 @@ use serde::{de, ser::SerializeSeq as _, Deserialize, Deserializer, Serializer};
 
 #[derive(Debug, Clone)]
 pub struct Persist {
-    /// Persist target executable
+    /// File or directory in the app directory
     pub target: String,
-    /// Persist name (without extension)
+    /// Name in the persist directory of the app, `target` if not set
     pub name: Option<String>,
 }
 
@@ -33,6 +33,14 @@ where
             formatter.write_str("string or list of strings")
         }
 
+        // `null` is written by the serializer when there is no field
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
+
         fn visit_str<E>(self, target: &str) -> Result<Self::Value, E>
         where
             E: de::Error,
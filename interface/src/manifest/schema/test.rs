@@ -20,3 +20,170 @@ async fn can_parse_all_local_manifest() {
         println!("Parsed all {} apps in {}", len, bucket.name);
     }
 }
+
+#[test]
+fn serialized_manifest_can_be_parsed_again() {
+    use crate::manifest::Manifest;
+    use std::str::FromStr as _;
+
+    let manifest = Manifest::from_str(
+        r#"{
+            "version": "1.0",
+            "homepage": "https://example.com",
+            "license": "MIT",
+            "url": "https://example.com/app.exe#/app.zip"
+        }"#,
+    )
+    .unwrap();
+    let serialized = serde_json::to_string(&manifest).unwrap();
+    let parsed = Manifest::from_str(&serialized).unwrap();
+
+    assert_eq!(parsed.version, "1.0");
+    assert!(parsed.bin.is_none());
+    assert!(parsed.persist.is_none());
+    let url = &parsed.url.unwrap()[0];
+    assert_eq!(url.url, "https://example.com/app.exe");
+    assert_eq!(url.file_name.as_deref(), Some("app.zip"));
+}
+
+#[test]
+fn download_url_target_name() {
+    use crate::manifest::DownloadUrl;
+    use std::str::FromStr as _;
+
+    let cases = [
+        ("https://example.com/app.zip", "app.zip"),
+        ("https://example.com/dl/app.7z?raw=true", "app.7z"),
+        ("https://example.com/download#/app.zip", "app.zip"),
+        ("https://example.com/app.exe#/dl.7z", "dl.7z"),
+    ];
+    for (url, name) in cases {
+        assert_eq!(DownloadUrl::from_str(url).unwrap().target_name(), name);
+    }
+}
+
+mod hash {
+    use std::str::FromStr as _;
+
+    use crate::manifest::{HashAlgorithm, Manifest, ManifestHash};
+
+    const SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    const SHA1: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+    const MD5: &str = "5d41402abc4b2a76b9719d911017c592";
+
+    fn manifest(hash: &str) -> crate::Result<Manifest> {
+        Manifest::from_str(&format!(
+            r#"{{
+                "version": "1.0",
+                "homepage": "https://example.com",
+                "license": "MIT",
+                "url": ["https://example.com/a.zip", "https://example.com/b.zip"],
+                "hash": {}
+            }}"#,
+            hash
+        ))
+    }
+
+    #[test]
+    fn parse_algorithms() {
+        let hash = ManifestHash::from_str(SHA256).unwrap();
+        assert_eq!(hash.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(hash.hex(), SHA256);
+
+        let hash = ManifestHash::from_str(&format!("sha1:{}", SHA1)).unwrap();
+        assert_eq!(hash.algorithm, HashAlgorithm::Sha1);
+        assert_eq!(hash.digest.len(), 20);
+
+        let hash = ManifestHash::from_str(&format!("MD5:{}", MD5.to_uppercase())).unwrap();
+        assert_eq!(hash.algorithm, HashAlgorithm::Md5);
+        assert_eq!(hash.hex(), MD5);
+
+        let hash = ManifestHash::from_str(&format!("sha512:{}", "ab".repeat(64))).unwrap();
+        assert_eq!(hash.algorithm, HashAlgorithm::Sha512);
+    }
+
+    #[test]
+    fn reject_invalid_hash() {
+        assert!(ManifestHash::from_str("xyz").is_err());
+        assert!(ManifestHash::from_str(&SHA256.replace('2', "g")).is_err());
+        assert!(ManifestHash::from_str(&SHA256[1..]).is_err());
+        assert!(ManifestHash::from_str(&format!("sha1:{}", SHA256)).is_err());
+        assert!(ManifestHash::from_str(&format!("crc32:{}", SHA1)).is_err());
+        assert!(manifest(&format!("[\"{}\", \"nothex\"]", SHA256)).is_err());
+    }
+
+    #[test]
+    fn serialize_losslessly() {
+        for hash in [
+            SHA256.to_string(),
+            format!("sha1:{}", SHA1),
+            format!("md5:{}", MD5),
+            format!("sha256:{}", SHA256),
+            format!("SHA1:{}", SHA1.to_uppercase()),
+        ] {
+            let parsed = ManifestHash::from_str(&hash).unwrap();
+            assert_eq!(parsed.to_string(), hash);
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(serde_json::from_str::<ManifestHash>(&json).unwrap(), parsed);
+        }
+
+        let m = manifest(&format!("[\"{}\", \"md5:{}\"]", SHA256, MD5)).unwrap();
+        let reparsed = Manifest::from_str(&serde_json::to_string(&m).unwrap()).unwrap();
+        assert_eq!(reparsed.hash, m.hash);
+
+        // Written the same way, but equal to the hash of the file however they are written
+        let upper = ManifestHash::from_str(&format!("sha256:{}", SHA256.to_uppercase())).unwrap();
+        assert_eq!(
+            upper.to_string(),
+            format!("sha256:{}", SHA256.to_uppercase())
+        );
+        let digest = upper.digest.clone();
+        assert_eq!(upper, ManifestHash::new(HashAlgorithm::Sha256, digest));
+    }
+
+    #[test]
+    fn pair_hashes_with_urls() {
+        let m = manifest(&format!("[\"{}\", \"sha1:{}\"]", SHA256, SHA1)).unwrap();
+        let pairs = m.architecture_current().url_hashes().unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].0.url, "https://example.com/a.zip");
+        assert_eq!(pairs[1].1.as_ref().unwrap().algorithm, HashAlgorithm::Sha1);
+
+        let m = manifest(&format!("\"{}\"", SHA256)).unwrap();
+        assert!(matches!(
+            m.architecture_current().url_hashes(),
+            Err(crate::Error::HashCountMismatch { urls: 2, hashes: 1 })
+        ));
+
+        let m = manifest("null").unwrap();
+        let pairs = m.architecture_current().url_hashes().unwrap();
+        assert!(pairs.iter().all(|(_, hash)| hash.is_none()));
+    }
+}
+
+#[test]
+fn cookie_header() {
+    use crate::manifest::Manifest;
+    use std::str::FromStr as _;
+
+    let manifest = |cookie: &str| {
+        Manifest::from_str(&format!(
+            r#"{{
+                "version": "1.0",
+                "homepage": "https://example.com",
+                "license": "MIT",
+                "cookie": {}
+            }}"#,
+            cookie
+        ))
+        .unwrap()
+    };
+    assert_eq!(
+        manifest(r#"{"oraclelicense": "accept-securebackup-cookie", "n": 1, "empty": null}"#)
+            .cookie_header()
+            .as_deref(),
+        Some("empty=; n=1; oraclelicense=accept-securebackup-cookie")
+    );
+    assert_eq!(manifest("{}").cookie_header(), None);
+    assert_eq!(manifest("null").cookie_header(), None);
+}
@@ -0,0 +1,208 @@
+//! Shims in [`SHIMS_DIR`]. Every shim `<name>` has a `<name>.shim` file next to its launchers,
+//! in the format of Scoop:
+//!
+//! ```text
+//! path = "C:\Users\me\scoop\apps\git\current\bin\git.exe"
+//! args = --some-arg
+//! ```
+//!
+//! The app that owns a shim is the one whose directory contains `path`. The apps that provide a
+//! shim are the installed apps whose current manifest has a `bin` entry with its name.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dir::{INSTALL_DIR, SHIMS_DIR},
+    error::Result,
+    installed_app::installed_apps,
+    manifest::Bin,
+};
+
+#[cfg(test)]
+mod test;
+
+/// Extensions of the files a shim consists of. `.shim` describes the shim, and the others run it.
+pub const FILE_EXTENSIONS: &[&str] = &["", ".shim", ".exe", ".cmd", ".ps1"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shim {
+    pub name: String,
+    /// Target executable
+    pub path: PathBuf,
+    /// Arguments passed before the ones given to the shim, as written on a command line
+    pub args: Option<String>,
+}
+
+impl Shim {
+    /// Parse the contents of `<name>.shim`
+    pub fn parse(name: &str, content: &str) -> Option<Self> {
+        let mut path = None;
+        let mut args = None;
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "path" => path = Some(PathBuf::from(value.trim_matches('"'))),
+                "args" if !value.is_empty() => args = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Some(Shim {
+            name: name.to_string(),
+            path: path?,
+            args,
+        })
+    }
+
+    /// Contents of `<name>.shim`
+    pub fn to_shim_file(&self) -> String {
+        let mut content = format!("path = \"{}\"\n", self.path.display());
+        if let Some(args) = &self.args {
+            content.push_str(&format!("args = {}\n", args));
+        }
+        content
+    }
+
+    /// Read the shim `name` in [`SHIMS_DIR`]. Returns `None` if there is no `.shim` file for it.
+    pub async fn read(name: &str) -> Result<Option<Self>> {
+        Self::read_in(&SHIMS_DIR, name).await
+    }
+
+    pub async fn read_in(dir: &Path, name: &str) -> Result<Option<Self>> {
+        match tokio::fs::read_to_string(dir.join(format!("{}.shim", name))).await {
+            Ok(content) => Ok(Self::parse(name, &content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Name of the app that owns the shim
+    pub fn owner(&self) -> Option<String> {
+        self.owner_in(&INSTALL_DIR.join("apps"))
+    }
+
+    /// Name of the app in `apps_dir` that owns the shim
+    pub fn owner_in(&self, apps_dir: &Path) -> Option<String> {
+        let relative = self.path.strip_prefix(apps_dir).ok()?;
+        let app = relative.components().next()?.as_os_str().to_str()?;
+        Some(app.to_string())
+    }
+}
+
+/// Files of the shim `name` in `dir` that exist
+pub async fn files_in(dir: &Path, name: &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for ext in FILE_EXTENSIONS {
+        let path = dir.join(format!("{}{}", name, ext));
+        if tokio::fs::try_exists(&path).await? {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Shims in [`SHIMS_DIR`], sorted by name
+pub async fn shims() -> Result<Vec<Shim>> {
+    shims_in(&SHIMS_DIR).await
+}
+
+/// Shims in `dir`, sorted by name. Launchers without a `.shim` file are not listed.
+pub async fn shims_in(dir: &Path) -> Result<Vec<Shim>> {
+    let mut shims = Vec::new();
+    if !dir.exists() {
+        return Ok(shims);
+    }
+    let mut readdir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = readdir.next_entry().await? {
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str().and_then(|n| n.strip_suffix(".shim")) else {
+            continue;
+        };
+        if let Some(shim) = Shim::read_in(dir, name).await? {
+            shims.push(shim);
+        }
+    }
+    shims.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(shims)
+}
+
+/// An installed app with a `bin` entry for a shim
+#[derive(Debug, Clone)]
+pub struct Provider {
+    pub app: String,
+    /// Current version of the app
+    pub version: String,
+    pub bin: Bin,
+}
+
+/// Apps that provide each shim, sorted by app name. Apps whose manifest can't be read, e.g.
+/// because they are not installed completely, are skipped.
+pub async fn providers() -> Result<BTreeMap<String, Vec<Provider>>> {
+    let mut apps = installed_apps().await?;
+    apps.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut providers = BTreeMap::<String, Vec<Provider>>::new();
+    for app in &apps {
+        let Ok(version) = app.current_version().await else {
+            continue;
+        };
+        let Ok(manifest) = version.manifest().await else {
+            continue;
+        };
+        for bin in manifest.architecture_current().bin.unwrap_or_default() {
+            providers
+                .entry(bin.name.clone())
+                .or_default()
+                .push(Provider {
+                    app: app.name.clone(),
+                    version: version.version.clone(),
+                    bin,
+                });
+        }
+    }
+    Ok(providers)
+}
+
+#[derive(Debug, Clone)]
+pub enum Repair {
+    /// Create the shim of the provider, replacing the existing one
+    Create(Provider),
+    /// Remove the shim, whose target no longer exists
+    Remove(String),
+}
+
+/// What `shim repair` does: every shim provided by an installed app is created again, for its
+/// owner if that still provides it and otherwise for the first provider. Shims that no app
+/// provides are removed if their target doesn't exist.
+pub fn repairs(
+    shims: &[Shim],
+    providers: &BTreeMap<String, Vec<Provider>>,
+    apps_dir: &Path,
+    target_exists: impl Fn(&Path) -> bool,
+) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    for (name, providers) in providers {
+        let owner = shims
+            .iter()
+            .find(|s| s.name == *name)
+            .and_then(|s| s.owner_in(apps_dir));
+        let provider = providers
+            .iter()
+            .find(|p| Some(&p.app) == owner.as_ref())
+            .or(providers.first());
+        if let Some(provider) = provider {
+            repairs.push(Repair::Create(provider.clone()));
+        }
+    }
+    for shim in shims {
+        if !providers.contains_key(&shim.name) && !target_exists(&shim.path) {
+            repairs.push(Repair::Remove(shim.name.clone()));
+        }
+    }
+    repairs
+}
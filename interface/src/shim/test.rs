@@ -0,0 +1,144 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use super::{files_in, repairs, shims_in, Provider, Repair, Shim};
+use crate::manifest::Bin;
+
+#[test]
+fn parse_shim_files() {
+    let shim = Shim::parse(
+        "git",
+        "path = \"C:\\scoop\\apps\\git\\current\\bin\\git.exe\"\r\nargs = --no-pager \"a b\"\r\n",
+    )
+    .unwrap();
+    assert_eq!(
+        shim.path,
+        PathBuf::from("C:\\scoop\\apps\\git\\current\\bin\\git.exe")
+    );
+    assert_eq!(shim.args.as_deref(), Some("--no-pager \"a b\""));
+
+    // Written by older versions of Scoop
+    let shim = Shim::parse("7z", "path = C:\\scoop\\apps\\7zip\\current\\7z.exe").unwrap();
+    assert_eq!(
+        shim.path,
+        PathBuf::from("C:\\scoop\\apps\\7zip\\current\\7z.exe")
+    );
+    assert_eq!(shim.args, None);
+
+    assert_eq!(Shim::parse("broken", "args = -v"), None);
+}
+
+#[test]
+fn format_round_trip() {
+    let shim = Shim {
+        name: "python".to_string(),
+        path: PathBuf::from("/scoop/apps/python/current/python.exe"),
+        args: Some("-X utf8".to_string()),
+    };
+    assert_eq!(
+        shim.to_shim_file(),
+        "path = \"/scoop/apps/python/current/python.exe\"\nargs = -X utf8\n"
+    );
+    assert_eq!(Shim::parse("python", &shim.to_shim_file()), Some(shim));
+}
+
+#[test]
+fn owner_is_app_containing_target() {
+    let apps = Path::new("/scoop/apps");
+    let shim = |path: &str| Shim {
+        name: "python".to_string(),
+        path: PathBuf::from(path),
+        args: None,
+    };
+    assert_eq!(
+        shim("/scoop/apps/python/current/python.exe").owner_in(apps),
+        Some("python".to_string())
+    );
+    assert_eq!(
+        shim("/scoop/apps/miniconda3/current/python.exe").owner_in(apps),
+        Some("miniconda3".to_string())
+    );
+    assert_eq!(shim("/usr/bin/python").owner_in(apps), None);
+}
+
+#[tokio::test]
+async fn read_shim_and_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("app.shim"),
+        "path = \"/apps/app/current/app\"",
+    )
+    .unwrap();
+    std::fs::write(tmp.path().join("app"), "#!/bin/sh").unwrap();
+    std::fs::write(tmp.path().join("other.cmd"), "@rem").unwrap();
+
+    let shim = Shim::read_in(tmp.path(), "app").await.unwrap().unwrap();
+    assert_eq!(shim.path, PathBuf::from("/apps/app/current/app"));
+    assert_eq!(Shim::read_in(tmp.path(), "other").await.unwrap(), None);
+
+    assert_eq!(
+        files_in(tmp.path(), "app").await.unwrap(),
+        [tmp.path().join("app"), tmp.path().join("app.shim")]
+    );
+
+    // `other` has only a launcher
+    let shims = shims_in(tmp.path()).await.unwrap();
+    assert_eq!(shims, [shim]);
+}
+
+fn provider(app: &str, name: &str) -> Provider {
+    Provider {
+        app: app.to_string(),
+        version: "1.0".to_string(),
+        bin: Bin {
+            target: format!("{}.exe", name),
+            name: name.to_string(),
+            args: None,
+        },
+    }
+}
+
+#[test]
+fn repair_keeps_owner_and_removes_broken_shims() {
+    let apps = Path::new("/scoop/apps");
+    let shim = |name: &str, path: &str| Shim {
+        name: name.to_string(),
+        path: PathBuf::from(path),
+        args: None,
+    };
+    let shims = [
+        shim("python", "/scoop/apps/python312/current/python.exe"),
+        shim("git", "/scoop/apps/removed/current/git.exe"),
+        shim("gone", "/scoop/apps/removed/current/gone.exe"),
+        shim("sh", "/usr/bin/sh"),
+    ];
+    let mut providers = BTreeMap::new();
+    providers.insert(
+        "python".to_string(),
+        vec![
+            provider("python", "python"),
+            provider("python312", "python"),
+        ],
+    );
+    providers.insert("git".to_string(), vec![provider("git", "git")]);
+    providers.insert("7z".to_string(), vec![provider("7zip", "7z")]);
+
+    let planned = repairs(&shims, &providers, apps, |path| path.starts_with("/usr"))
+        .into_iter()
+        .map(|r| match r {
+            Repair::Create(p) => format!("create {} of {}", p.bin.name, p.app),
+            Repair::Remove(name) => format!("remove {}", name),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        planned,
+        [
+            "create 7z of 7zip",
+            "create git of git",
+            "create python of python312",
+            "remove gone",
+        ]
+    );
+}
@@ -0,0 +1,186 @@
+use std::{
+    cmp::Ordering,
+    convert::Infallible,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod test;
+
+/// Version string of an app, ordered with the same rules as `Compare-Version` of Scoop.
+///
+/// - Versions are split into parts at `-` (`+` is treated as `-`), and letters are separated
+///   from digits, so `1.0rc1` becomes `1.0`, `rc`, `1`.
+/// - Parts containing `.` or `_` are compared recursively with that delimiter.
+/// - Numeric parts are compared as numbers, others as case-insensitive strings. A number
+///   compared with a string is compared as a string.
+/// - If one version has more parts, it is newer unless the extra part is a pre-release tag
+///   (`alpha`, `beta`, `rc` or `pre`), so `1.1-beta` < `1.1` < `1.1.1`.
+/// - `nightly` versions are all equal to each other.
+///
+/// Because of the above, different strings can be equal (e.g. `1.0` and `1.00`), so this type
+/// does not implement `Hash`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct Version(String);
+
+impl Version {
+    pub fn new(version: impl Into<String>) -> Self {
+        Version(version.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_nightly(&self) -> bool {
+        self.0 == "nightly" || self.0.starts_with("nightly-")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Number(u64),
+    String(String),
+}
+
+impl Part {
+    fn as_string(&self) -> String {
+        match self {
+            Part::Number(n) => n.to_string(),
+            Part::String(s) => s.clone(),
+        }
+    }
+
+    fn is_pre_release(&self) -> bool {
+        match self {
+            Part::Number(_) => false,
+            Part::String(s) => ["alpha", "beta", "rc", "pre"]
+                .iter()
+                .any(|tag| s.contains(tag)),
+        }
+    }
+}
+
+fn split_version(version: &str, delimiter: char) -> Vec<Part> {
+    let mut separated = String::with_capacity(version.len());
+    let mut in_letters = false;
+    for c in version.chars() {
+        let is_letter = c.is_ascii_alphabetic();
+        if is_letter != in_letters {
+            separated.push(delimiter);
+        }
+        in_letters = is_letter;
+        separated.push(c);
+    }
+
+    separated
+        .split(delimiter)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            if p.bytes().all(|b| b.is_ascii_digit()) {
+                if let Ok(n) = p.parse() {
+                    return Part::Number(n);
+                }
+            }
+            Part::String(p.to_lowercase())
+        })
+        .collect()
+}
+
+fn compare(a: &str, b: &str, delimiter: char) -> Ordering {
+    if a.eq_ignore_ascii_case(b) {
+        return Ordering::Equal;
+    }
+
+    let a = split_version(a, delimiter);
+    let b = split_version(b, delimiter);
+
+    if matches!((a.first(), b.first()), (Some(Part::String(x)), Some(Part::String(y))) if x == "nightly" && y == "nightly")
+    {
+        return Ordering::Equal;
+    }
+
+    for i in 0..a.len().max(b.len()) {
+        let (a, b) = match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(a), None) => {
+                return if a.is_pre_release() {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+            (None, Some(b)) => {
+                return if b.is_pre_release() {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let ordering = match (a, b) {
+            (Part::Number(a), Part::Number(b)) => a.cmp(b),
+            (a, b) => {
+                let (a, b) = (a.as_string(), b.as_string());
+                if let Some(d) = ['.', '_']
+                    .into_iter()
+                    .find(|d| a.contains(*d) || b.contains(*d))
+                {
+                    compare(&a, &b, d)
+                } else {
+                    a.cmp(&b)
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&self.0.replace('+', "-"), &other.0.replace('+', "-"), '-')
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl FromStr for Version {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Version(s.to_string()))
+    }
+}
+
+impl From<&str> for Version {
+    fn from(s: &str) -> Self {
+        Version(s.to_string())
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
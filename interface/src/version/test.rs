@@ -0,0 +1,139 @@
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+
+use super::Version;
+
+/// (a, b, a.cmp(b))
+const CASES: &[(&str, &str, Ordering)] = &[
+    // equal
+    ("1.0", "1.0", Equal),
+    ("1.2.3", "1.2.3", Equal),
+    ("1.0", "1.00", Equal),
+    ("v1.0", "V1.0", Equal),
+    ("1.0-beta", "1.0-BETA", Equal),
+    // numeric parts
+    ("1.0", "2.0", Less),
+    ("2.0", "1.0", Greater),
+    ("1.2", "1.10", Less),
+    ("1.9.9", "1.10.0", Less),
+    ("0.9", "0.10", Less),
+    ("10", "9", Greater),
+    ("1.2.3", "1.2.3.4", Less),
+    ("1.2.3.4", "1.2.3", Greater),
+    ("1.0", "1.0.0", Less),
+    ("1.0.1", "1.0", Greater),
+    ("4.2.0.1", "4.2.1", Less),
+    // pre-release tags
+    ("1.1-alpha", "1.1", Less),
+    ("1.1", "1.1-beta", Greater),
+    ("1.1-rc", "1.1", Less),
+    ("1.1-pre", "1.1", Less),
+    ("1.1-preview", "1.1", Less),
+    ("1.1-alpha", "1.1-beta", Less),
+    ("1.1-beta", "1.1-rc", Less),
+    ("1.1-beta1", "1.1-beta2", Less),
+    ("1.1-beta2", "1.1-beta10", Less),
+    ("1.1-rc1", "1.1", Less),
+    ("1.1-rc1", "1.2-alpha1", Less),
+    ("1.0-beta", "1.0.1", Less),
+    // build metadata is treated as post-release
+    ("1.0", "1.0-1", Less),
+    ("1.0", "1.0+1", Less),
+    ("1.0+1", "1.0+2", Less),
+    ("1.0-1", "1.0+2", Less),
+    ("1.0-2", "1.0+1", Greater),
+    // mixed alphanumeric
+    ("1.2.3-beta2", "1.2.3", Less),
+    ("1.2.3-beta2", "1.2.3-beta3", Less),
+    ("1.2.3-beta2", "1.2.4-beta1", Less),
+    ("1.0rc1", "1.0", Less),
+    ("1.0rc1", "1.0rc2", Less),
+    ("1.0b1", "1.0a2", Greater),
+    ("1.0a1", "1.0", Greater),
+    ("1.0", "1.0a", Less),
+    ("2.3a", "2.3b", Less),
+    ("1.16.0b", "1.16.0", Greater),
+    ("1.1.1k", "1.1.1l", Less),
+    ("1.1.1w", "3.0.0", Less),
+    ("v1.2", "v1.3", Less),
+    ("r123", "r124", Less),
+    ("build-99", "build-100", Less),
+    // underscores
+    ("1_2", "1_3", Less),
+    ("1_10", "1_9", Greater),
+    ("8u372", "8u382", Less),
+    ("11.0.2_9", "11.0.2_10", Less),
+    ("11.0.2_9", "11.0.3_1", Less),
+    // date-style versions
+    ("2021-01-01", "2021-01-02", Less),
+    ("2021-01-31", "2021-02-01", Less),
+    ("2020-12-31", "2021-01-01", Less),
+    ("20210101", "20210102", Less),
+    ("20191231", "20200101", Less),
+    ("2023.12.31", "2024.01.01", Less),
+    ("2024.1.2", "2024.01.02", Equal),
+    ("22.04", "22.10", Less),
+    ("2024-01-01", "2024-01-01", Equal),
+    // nightly
+    ("nightly", "nightly", Equal),
+    ("nightly-20240101", "nightly-20240202", Equal),
+    ("nightly", "nightly-20240101", Equal),
+    // long numbers
+    ("1.20240101123045", "1.20240101123046", Less),
+    ("99999999999999999999", "1", Greater),
+];
+
+#[test]
+fn compare_versions() {
+    let mut failed = Vec::new();
+    for (a, b, expected) in CASES {
+        let actual = Version::from(*a).cmp(&Version::from(*b));
+        if actual != *expected {
+            failed.push(format!(
+                "{} vs {}: expected {:?}, got {:?}",
+                a, b, expected, actual
+            ));
+        }
+    }
+    assert!(failed.is_empty(), "\n{}", failed.join("\n"));
+}
+
+#[test]
+fn comparison_is_antisymmetric() {
+    for (a, b, expected) in CASES {
+        assert_eq!(
+            Version::from(*b).cmp(&Version::from(*a)),
+            expected.reverse(),
+            "{} vs {}",
+            b,
+            a
+        );
+    }
+}
+
+#[test]
+fn sort_versions() {
+    let mut versions = ["1.10", "1.2-beta", "1.2", "1.0rc1", "1.0", "1.2.1"]
+        .into_iter()
+        .map(Version::from)
+        .collect::<Vec<_>>();
+    versions.sort();
+    let sorted = versions.iter().map(|v| v.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        sorted,
+        ["1.0rc1", "1.0", "1.2-beta", "1.2", "1.2.1", "1.10"]
+    );
+}
+
+#[test]
+fn nightly() {
+    assert!(Version::from("nightly").is_nightly());
+    assert!(Version::from("nightly-20240101").is_nightly());
+    assert!(!Version::from("1.0").is_nightly());
+}
+
+#[test]
+fn serde_is_transparent() {
+    let version: Version = serde_json::from_str("\"1.2.3\"").unwrap();
+    assert_eq!(version.as_str(), "1.2.3");
+    assert_eq!(serde_json::to_string(&version).unwrap(), "\"1.2.3\"");
+}
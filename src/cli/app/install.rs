@@ -1,20 +1,25 @@
+use std::collections::HashSet;
+
 use anyhow::Context;
 use clap::Args;
 use interface::{
     bucket::get_buckets,
-    bucket_app::{BucketAppName, BucketsAppsRepository},
+    bucket_app::{BucketApp, BucketAppName, BucketsAppsRepository},
+    installed_app::InstalledApp,
+    manifest::Manifest,
 };
 
 use crate::cli::CliResult;
 
-mod download;
-mod env;
+pub(crate) mod download;
+pub(super) mod env;
 mod installer;
 mod link;
 mod persist;
 mod resolve;
-mod run_script;
-mod shortcut;
+pub(super) mod run_script;
+pub(crate) mod shim;
+pub(super) mod shortcut;
 
 #[derive(Debug, Args)]
 pub struct InstallArgs {
@@ -22,10 +27,16 @@ pub struct InstallArgs {
     pub apps: Vec<BucketAppName>,
     #[clap(long, default_value_t = false)]
     pub no_hash_check: bool,
+    /// Number of times to retry a download that failed with a network or server error
+    #[clap(long, default_value_t = 3)]
+    pub retries: u32,
+    /// Install only from the cache, without downloading anything
+    #[clap(long, default_value_t = false)]
+    pub offline: bool,
 }
 
 pub async fn start(opts: InstallArgs) -> CliResult {
-    start_inner(opts).await.map_err(|e| e.to_string())
+    start_inner(opts).await.map_err(|e| format!("{:#}", e))
 }
 
 pub async fn start_inner(opts: InstallArgs) -> anyhow::Result<()> {
@@ -45,43 +56,161 @@ pub async fn start_inner(opts: InstallArgs) -> anyhow::Result<()> {
         install_apps.extend(to_install);
     }
 
-    // TODO: Error handling
-    download::download(&install_apps).await;
+    // An app can be given twice or be a dependency of another one, but is only downloaded and
+    // installed once
+    let mut names = HashSet::new();
+    install_apps.retain(|(app, _)| names.insert(app.name.clone()));
 
+    // Installing over the live version would break it, new versions are installed by `upgrade`
+    let mut to_install = Vec::new();
     for (app, manifest) in install_apps {
-        let arch_m = manifest.architecture_current();
+        match InstalledApp::from_name(&app.name).current_version().await {
+            Ok(current) => println!(
+                "{} {} is already installed, use `upgrade` to install a new version",
+                app.name, current.version
+            ),
+            Err(_) => to_install.push((app, manifest)),
+        }
+    }
+    let install_apps = to_install;
+
+    let options = download::DownloadOptions {
+        hash_check: !opts.no_hash_check,
+        retry: download::RetryPolicy::new(opts.retries),
+        offline: opts.offline,
+    };
+    let results = download::download(&install_apps, options).await?;
+    download::ensure_downloaded(&results)?;
+
+    for (app, manifest) in &install_apps {
+        install_app(app, manifest).await?;
+        println!("Installed {}", app.name);
+    }
+
+    Ok(())
+}
 
-        if let Some(pre_install) = &arch_m.pre_install {
-            run_script::run_script(pre_install)
-                .await
-                .context("Failed to run pre-install script")?;
+/// Install one app whose files are already downloaded to the cache.
+/// The app is installed into its version directory and `current` is switched to it only after
+/// all other steps succeeded, so an existing version keeps working if this fails. The version
+/// directory is removed if a step fails, see [`clean_up_failed`].
+pub(super) async fn install_app(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
+    let installed = InstalledApp::from_name(&app.name);
+    if installed.path().join(&manifest.version).exists() {
+        anyhow::bail!("{} {} is already installed", app.name, manifest.version);
+    }
+    let is_new = installed.current_version().await.is_err();
+
+    // Shims of the version being replaced, to remove the ones the new version doesn't have
+    let old_shims = installed_shims(&app.name).await;
+    let shims = match install_version(app, manifest).await {
+        Ok(shims) => shims,
+        Err(e) => {
+            clean_up_failed(app, manifest, is_new).await;
+            return Err(e);
         }
+    };
 
-        installer::extract(app, &manifest).await?;
-        installer::run_installer(app, &manifest).await?;
+    let stale = old_shims
+        .into_iter()
+        .filter(|name| !shims.contains(name))
+        .collect::<Vec<_>>();
+    shim::remove_shims(&app.name, &stale, &stale)
+        .await
+        .context("Failed to remove shims of the previous version")?;
 
-        link::link_to_current(app, &manifest.version).await?;
+    Ok(())
+}
 
-        shortcut::create_shims(app, &manifest).await?;
-        shortcut::create_startmenu_shortcuts(app, &manifest).await?;
+/// Install the version of `manifest` and switch `current` to it. Returns the names of its shims.
+async fn install_version(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<Vec<String>> {
+    let arch_m = manifest.architecture_current();
 
-        installer::install_psmodule(app, &manifest).await?;
+    if let Some(pre_install) = &arch_m.pre_install {
+        run_script::run_script(pre_install, &app.name, &manifest.version)
+            .await
+            .context("Failed to run pre-install script")?;
+    }
 
-        env::path(app, &manifest).await?;
-        env::set_env(app, &manifest).await?;
+    installer::extract(app, manifest).await?;
+    installer::run_installer(app, manifest).await?;
 
-        persist::persist(app, &manifest).await?;
+    let shims = shim::create_shims(app, manifest).await?;
+    shortcut::create_startmenu_shortcuts(app, manifest).await?;
 
-        if let Some(post_install) = &arch_m.post_install {
-            run_script::run_script(post_install)
-                .await
-                .context("Failed to run post-install script")?;
-        }
+    installer::install_psmodule(app, manifest).await?;
 
-        installer::create_info(app, &manifest).await?;
+    env::path(app, manifest).await?;
+    env::set_env(app, manifest).await?;
 
-        println!("Installed {}", app.name);
+    persist::persist(app, manifest).await?;
+
+    if let Some(post_install) = &arch_m.post_install {
+        run_script::run_script(post_install, &app.name, &manifest.version)
+            .await
+            .context("Failed to run post-install script")?;
     }
 
-    Ok(())
+    installer::create_info(app, manifest, &shims).await?;
+
+    link::link_to_current(app, &manifest.version).await?;
+
+    Ok(shims)
+}
+
+/// Remove what a failed [`install_version`] left behind: the version directory, and for an app
+/// that had no version installed, also its shims, shortcuts, PATH entries, variables and
+/// directory, so that it is not installed at all. `current` of an installed app still points to
+/// the previous version, whose shims are rewritten by the next install or `shim repair`.
+async fn clean_up_failed(app: &BucketApp<'_>, manifest: &Manifest, is_new: bool) {
+    let app_dir = InstalledApp::from_name(&app.name).path();
+    let mut results = vec![remove_dir_all(&app_dir.join(&manifest.version)).await];
+    if is_new {
+        let bins = manifest
+            .architecture_current()
+            .bin
+            .unwrap_or_default()
+            .into_iter()
+            .map(|bin| bin.name)
+            .collect::<Vec<_>>();
+        // The shims of the `bin` entries were created by this install, which failed before
+        // recording them
+        results.extend([
+            shim::remove_shims(&app.name, &bins, &bins).await,
+            shortcut::remove_startmenu_shortcuts(manifest).await,
+            env::remove_path(&app.name, manifest).await,
+            env::remove_env(manifest).await,
+            remove_dir_all(&app_dir).await,
+        ]);
+    }
+    for e in results.into_iter().filter_map(Result::err) {
+        eprintln!(
+            "{}Failed to clean up the failed install of {}: {:#}",
+            console::style("Warning: ").yellow(),
+            app.name,
+            e
+        );
+    }
+}
+
+async fn remove_dir_all(path: &std::path::Path) -> anyhow::Result<()> {
+    match tokio::fs::remove_dir_all(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(anyhow::Error::new(e).context(format!("Failed to remove {}", path.display())))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Shims recorded by the current version of the app, if it is installed
+async fn installed_shims(app_name: &str) -> Vec<String> {
+    let app = InstalledApp::from_name(app_name);
+    let Ok(version) = app.current_version().await else {
+        return Vec::new();
+    };
+    version
+        .install_info()
+        .await
+        .map(|info| info.shims)
+        .unwrap_or_default()
 }
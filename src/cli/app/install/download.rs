@@ -1,84 +1,652 @@
-use std::{fmt::Write, path::PathBuf};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
+use anyhow::Context as _;
 use futures_util::StreamExt as _;
-use indicatif::{MultiProgress, ProgressState, ProgressStyle};
-use interface::{bucket_app::BucketApp, dir::CACHE_DIR, manifest::Manifest};
-use tokio::{fs::File, io::AsyncWriteExt as _};
+use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use interface::{
+    bucket_app::BucketApp,
+    cache::{cache_path, cache_version, PART_SUFFIX},
+    config::{Config, NetworkConfig},
+    manifest::{HashAlgorithm, Manifest, ManifestHash},
+};
+use reqwest::{header, StatusCode};
+use sha2::Digest as _;
+use tabled::settings::Style;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+};
+
+#[cfg(test)]
+mod test;
 
 const DOWNLOAD_CONCURRENCY: usize = 4;
 
-pub async fn download<'a>(install_apps: &'a [(&'a BucketApp<'a>, Manifest)]) {
+const USER_AGENT: &str = concat!("scoop-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Same limit as the default redirect policy of reqwest, for all redirects of a download
+const MAX_REDIRECTS: usize = 10;
+
+/// Path the file is downloaded to before it is complete and verified.
+/// It is kept when the download fails, so that the next download can resume it.
+fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_owned();
+    part.push(PART_SUFFIX);
+    PathBuf::from(part)
+}
+
+/// Client shared by all downloads, with `network` applied the same way as to buckets.
+/// Redirects are not followed by the client, but by [`DownloadRequest::send`].
+pub fn client(network: &NetworkConfig) -> anyhow::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .redirect(reqwest::redirect::Policy::none());
+    if network.proxy.is_some() {
+        let network = network.clone();
+        builder = builder.proxy(reqwest::Proxy::custom(move |url| {
+            network.proxy_for(url.host_str()?).map(|p| p.to_string())
+        }));
+    }
+    if let Some(ca_bundle) = &network.ca_bundle {
+        let pem = std::fs::read(ca_bundle)
+            .with_context(|| format!("Failed to read CA bundle {}", ca_bundle.display()))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid CA bundle {}", ca_bundle.display()))?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if network.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+/// Whether a redirect from `from` to `to` may keep the headers for the host of `from`: the
+/// scheme, host and port are the same, so they are not sent to another server or in cleartext
+fn is_same_origin(from: &reqwest::Url, to: &reqwest::Url) -> bool {
+    from.origin() == to.origin()
+}
+
+/// Url to download and the headers to send with it
+#[derive(Debug, Clone)]
+pub struct DownloadRequest {
+    pub url: String,
+    /// Headers in `config` for the host of `url`, which are not sent to other hosts
+    pub headers: header::HeaderMap,
+    /// Cookies of the manifest, which are sent to all hosts the download is redirected to
+    pub cookie: Option<header::HeaderValue>,
+}
+
+impl DownloadRequest {
+    /// Request for `url` of `manifest`, with its cookies and the headers in `config` for the host
+    pub fn new(url: &str, manifest: &Manifest, config: &Config) -> anyhow::Result<Self> {
+        let mut headers = header::HeaderMap::new();
+        let host = reqwest::Url::parse(url)
+            .with_context(|| format!("Invalid url: {}", url))?
+            .host_str()
+            .map(|h| h.to_string());
+        if let Some(host) = host {
+            for (name, value) in config.headers_for(&host) {
+                headers.append(
+                    header::HeaderName::try_from(name)
+                        .with_context(|| format!("Invalid header name for {}: {}", host, name))?,
+                    header::HeaderValue::try_from(value)
+                        .with_context(|| format!("Invalid value of {} for {}", name, host))?,
+                );
+            }
+        }
+        let cookie = manifest
+            .cookie_header()
+            .map(header::HeaderValue::try_from)
+            .transpose()
+            .context("Invalid cookie")?;
+        Ok(Self {
+            url: url.to_string(),
+            headers,
+            cookie,
+        })
+    }
+
+    /// Send the request, from byte `offset` if it is not 0. Redirects are followed up to
+    /// [`MAX_REDIRECTS`] times. The headers for the host of `url` are only sent as long as the
+    /// redirects stay on its origin, and the cookies are sent to all of them.
+    async fn send(
+        &self,
+        client: &reqwest::Client,
+        offset: u64,
+    ) -> anyhow::Result<reqwest::Response> {
+        let get = |url: reqwest::Url, with_headers: bool| {
+            let mut get = client.get(url);
+            if with_headers {
+                get = get.headers(self.headers.clone());
+            }
+            if let Some(cookie) = &self.cookie {
+                get = get.header(header::COOKIE, cookie);
+            }
+            if offset > 0 {
+                get = get.header(header::RANGE, format!("bytes={}-", offset));
+            }
+            get
+        };
+
+        let mut with_headers = true;
+        let mut resp = get(reqwest::Url::parse(&self.url)?, with_headers)
+            .send()
+            .await?;
+        let mut redirects = 0;
+        loop {
+            if !resp.status().is_redirection() {
+                return Ok(resp);
+            }
+            let location = resp
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| resp.url().join(location).ok());
+            // Not a file, e.g. `304 Not Modified` or `300 Multiple Choices`
+            let Some(location) = location else {
+                anyhow::bail!(
+                    "Server responded with {} without a valid location for {}",
+                    resp.status(),
+                    self.url
+                );
+            };
+            if redirects == MAX_REDIRECTS {
+                anyhow::bail!("Too many redirects for {}", self.url);
+            }
+            redirects += 1;
+            with_headers = with_headers && is_same_origin(resp.url(), &location);
+            resp = get(location, with_headers).send().await?;
+        }
+    }
+}
+
+/// How failed downloads are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt
+    pub retries: u32,
+    /// Delay before the first retry. It is doubled for each retry.
+    pub base_delay: Duration,
+    /// Upper limit of the delay, including `Retry-After` of the server
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(retries: u32) -> Self {
+        RetryPolicy {
+            retries,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// Delay before retrying after the `attempt`th attempt failed with `error`.
+    /// Returns `None` if the error is not transient, or there are no retries left.
+    fn delay(&self, error: &anyhow::Error, attempt: u32) -> Option<Duration> {
+        if attempt > self.retries {
+            return None;
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+
+        if let Some(e) = error.downcast_ref::<TransientError>() {
+            return match e {
+                TransientError::Status {
+                    retry_after: Some(retry_after),
+                    ..
+                } => Some((*retry_after).min(self.max_delay)),
+                _ => Some(backoff),
+            };
+        }
+        if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+            // Connection refused or reset (also while reading the body), and timeouts.
+            // Other errors (e.g. an invalid url or `404`) will fail again.
+            if e.is_connect() || e.is_timeout() || e.is_request() || e.is_body() || e.is_decode() {
+                return Some(backoff);
+            }
+        }
+        None
+    }
+}
+
+/// Errors of a download that may succeed if retried
+#[derive(Debug)]
+enum TransientError {
+    /// `5xx` or `429 Too Many Requests`
+    Status {
+        status: StatusCode,
+        retry_after: Option<Duration>,
+    },
+    /// The connection was closed before `Content-Length` bytes were received
+    Incomplete { received: u64, expected: u64 },
+}
+
+impl std::fmt::Display for TransientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransientError::Status { status, .. } => write!(f, "Server responded with {}", status),
+            TransientError::Incomplete { received, expected } => write!(
+                f,
+                "Download is incomplete: received {} of {} bytes",
+                received, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TransientError {}
+
+/// `Retry-After` header, either in seconds or an HTTP date
+fn retry_after(headers: &header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means the request can be retried now
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Options of [`download`]
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadOptions {
+    /// Verify files with the `hash` of the manifest
+    pub hash_check: bool,
+    pub retry: RetryPolicy,
+    /// Only use the cache, and fail if a file is not in it
+    pub offline: bool,
+}
+
+/// Result of downloading one url
+pub struct DownloadResult {
+    pub app: String,
+    pub url: String,
+    /// Number of attempts, including retries. `0` if the file was in the cache.
+    pub attempts: u32,
+    pub result: anyhow::Result<()>,
+}
+
+/// Download files of apps to the cache, retrying transient errors as `options.retry` says.
+/// Files already in the cache are not downloaded again, if their hash matches.
+///
+/// Returns the result of each url. An `Err` is only returned if a manifest is invalid, or
+/// a file is not in the cache in the offline mode.
+pub async fn download<'a>(
+    install_apps: &'a [(&'a BucketApp<'a>, Manifest)],
+    options: DownloadOptions,
+) -> anyhow::Result<Vec<DownloadResult>> {
     let m = MultiProgress::new();
+    let config = crate::cli::load_config().await?;
+    let client = client(&config.network)?;
+    let mut results = Vec::new();
+    let mut not_cached = Vec::new();
     let mut download_futures = Vec::new();
 
     for (app, manifest) in install_apps {
-        let version = if manifest.version == "nightly" {
-            format!("nightly-{}", chrono::Utc::now().format("%Y-%m-%d"))
-        } else {
-            manifest.version.clone()
-        };
+        let version = cache_version(&manifest.version);
 
-        let urls = manifest.architecture_current().url;
-        let urls = urls.unwrap_or_default();
+        let urls = manifest
+            .architecture_current()
+            .url_hashes()
+            .with_context(|| format!("Invalid manifest of {}", app.name))?;
         let name = app.name.clone();
         let url_count = urls.len();
-        for (i, url) in urls.into_iter().enumerate() {
-            let cache_file_name = format!(
-                "{}-{}-{}",
-                &name,
-                &version,
-                sanitize_filename::sanitize(&url.url)
-            );
+        for (i, (url, hash)) in urls.into_iter().enumerate() {
+            let path = cache_path(&name, &manifest.version, &url);
             let show_name = if url_count == 1 {
                 format!("{} {}", &name, &version)
             } else {
                 format!("{} {} ({})", &name, &version, i + 1)
             };
+            // Nightly versions change every day, so they can't have a hash
+            let expected_hash =
+                hash.filter(|_| options.hash_check && manifest.version != "nightly");
+
+            let cached = is_cached(&path, expected_hash.as_ref())
+                .await
+                .with_context(|| format!("Failed to check the cache of {}", show_name))?;
+            if cached {
+                println!("Using cached {}", show_name);
+                results.push(DownloadResult {
+                    app: name.clone(),
+                    url: url.url,
+                    attempts: 0,
+                    result: Ok(()),
+                });
+                continue;
+            }
+            if options.offline {
+                not_cached.push(format!("{} ({})", show_name, url.url));
+                continue;
+            }
+            let request = DownloadRequest::new(&url.url, manifest, &config)
+                .with_context(|| format!("Invalid download of {}", show_name))?;
+
+            let client = client.clone();
+            let name = name.clone();
             let m = m.clone();
             download_futures.push(async move {
-                download_to_cache(url.url, cache_file_name, show_name, m.clone()).await
+                let pb = m.add(progress_bar(&show_name));
+                let (attempts, result) = download_with_retry(
+                    &client,
+                    &request,
+                    &path,
+                    &show_name,
+                    expected_hash,
+                    &pb,
+                    options.retry,
+                )
+                .await;
+                DownloadResult {
+                    app: name,
+                    url: url.url,
+                    attempts,
+                    result,
+                }
             })
         }
     }
-    println!("Downloading {} files...", download_futures.len());
+
+    if !not_cached.is_empty() {
+        anyhow::bail!(
+            "Files are not in the cache, so they can't be installed offline:\n\t{}",
+            not_cached.join("\n\t")
+        );
+    }
+
+    if !download_futures.is_empty() {
+        println!("Downloading {} files...", download_futures.len());
+    }
     let stream = futures::stream::iter(download_futures).buffer_unordered(DOWNLOAD_CONCURRENCY);
-    let _results = stream.collect::<Vec<_>>().await;
+    results.extend(stream.collect::<Vec<_>>().await);
+    Ok(results)
 }
 
-async fn download_to_cache(
-    url: String,
-    cache_file_name: String,
-    show_name: String,
-    progress: MultiProgress,
-) -> Result<(), anyhow::Error> {
-    let mut file = File::create(CACHE_DIR.join(&cache_file_name)).await?;
+/// Whether the file at `path` is in the cache and matches `expected_hash`, if given.
+/// A cached file with a different hash is removed, so that it is downloaded again.
+async fn is_cached(path: &Path, expected_hash: Option<&ManifestHash>) -> anyhow::Result<bool> {
+    if !tokio::fs::try_exists(path).await? {
+        return Ok(false);
+    }
+    let Some(expected) = expected_hash else {
+        return Ok(true);
+    };
 
-    let pb = progress.add(indicatif::ProgressBar::new(100));
+    let mut hasher = Hasher::new(expected.algorithm);
+    hasher.update_from_file(path).await?;
+    if hasher.finalize() == *expected {
+        Ok(true)
+    } else {
+        tokio::fs::remove_file(path).await?;
+        Ok(false)
+    }
+}
+
+/// Fail with a table of the failed downloads, if any
+pub fn ensure_downloaded(results: &[DownloadResult]) -> anyhow::Result<()> {
+    let failed = results
+        .iter()
+        .filter_map(|r| r.result.as_ref().err().map(|e| (r, e)))
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["App", "Url", "Attempts", "Error"]);
+    for (r, e) in &failed {
+        builder.push_record([
+            r.app.clone(),
+            r.url.clone(),
+            r.attempts.to_string(),
+            format!("{:#}", e),
+        ]);
+    }
+    let table = builder.build().with(Style::rounded()).to_string();
+    println!("{}", table);
+
+    anyhow::bail!("Failed to download {} files", failed.len());
+}
+
+fn progress_bar(show_name: &str) -> ProgressBar {
+    let pb = ProgressBar::new(100);
     pb.set_style(ProgressStyle::with_template("{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
         .unwrap()
         .with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
         .progress_chars("#>-"));
-
     pb.set_message(show_name.to_string());
+    pb
+}
 
-    let resp = reqwest::get(url).await?;
-    let length = resp.content_length();
+/// [`download_to_cache`] until it succeeds or fails with an error that is not transient.
+/// Retries resume the part downloaded before. Returns the number of attempts and the result.
+async fn download_with_retry(
+    client: &reqwest::Client,
+    request: &DownloadRequest,
+    path: &Path,
+    show_name: &str,
+    expected_hash: Option<ManifestHash>,
+    pb: &ProgressBar,
+    retry: RetryPolicy,
+) -> (u32, anyhow::Result<()>) {
+    let mut attempt = 1;
+    loop {
+        match download_to_cache(client, request, path, expected_hash.clone(), pb).await {
+            Ok(()) => {
+                pb.finish_with_message(format!("Downloaded {}", show_name));
+                return (attempt, Ok(()));
+            }
+            Err(e) => match retry.delay(&e, attempt) {
+                Some(delay) => {
+                    pb.set_message(format!(
+                        "{} (retry {}/{} in {:.1}s: {})",
+                        show_name,
+                        attempt,
+                        retry.retries,
+                        delay.as_secs_f64(),
+                        e
+                    ));
+                    tokio::time::sleep(delay).await;
+                    pb.set_message(show_name.to_string());
+                    attempt += 1;
+                }
+                None => {
+                    pb.abandon_with_message(format!("Failed {}", show_name));
+                    return (attempt, Err(e));
+                }
+            },
+        }
+    }
+}
+
+/// Download `request` to `path`, through its [`part_path`]. The file is renamed to `path` only after
+/// it is complete and its hash is verified, so a file in the cache is never truncated.
+/// An existing part file is resumed with a `Range` request if the server supports it.
+async fn download_to_cache(
+    client: &reqwest::Client,
+    request: &DownloadRequest,
+    path: &Path,
+    expected_hash: Option<ManifestHash>,
+    pb: &ProgressBar,
+) -> Result<(), anyhow::Error> {
+    let part = part_path(path);
+    let mut offset = match tokio::fs::metadata(&part).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
 
+    let mut resp = request.send(client, offset).await?;
+    if offset > 0 && resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The part file is not a prefix of the file anymore, e.g. it was updated on the server
+        offset = 0;
+        resp = request.send(client, 0).await?;
+    }
+    let status = resp.status();
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(TransientError::Status {
+            status,
+            retry_after: retry_after(resp.headers()),
+        }
+        .into());
+    }
+    let mut resp = resp.error_for_status()?;
+
+    if offset > 0 {
+        if resp.status() != StatusCode::PARTIAL_CONTENT {
+            // The server ignored `Range` and sends the whole file
+            offset = 0;
+        } else if content_range_start(&resp) != Some(offset) {
+            // The part file can't be continued with that range, so the whole file is downloaded
+            offset = 0;
+            resp = request.send(client, 0).await?.error_for_status()?;
+        }
+    }
+
+    let length = resp.content_length().map(|len| offset + len);
     pb.set_length(length.unwrap_or(0));
+    pb.set_position(offset);
+
+    let mut hasher = expected_hash.as_ref().map(|h| Hasher::new(h.algorithm));
+    let mut file = if offset > 0 {
+        if let Some(hasher) = &mut hasher {
+            hasher.update_from_file(&part).await?;
+        }
+        OpenOptions::new().append(true).open(&part).await?
+    } else {
+        File::create(&part).await?
+    };
 
     let mut stream = resp.bytes_stream();
 
-    let mut downloaded = 0;
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result?;
-        downloaded += chunk.len() as u64;
-        pb.set_position(downloaded);
-        file.write_all(&chunk).await?;
+    let mut downloaded = offset;
+    let res = async {
+        while let Some(chunk_result) = stream.next().await {
+            let chunk = chunk_result?;
+            downloaded += chunk.len() as u64;
+            pb.set_position(downloaded);
+            if let Some(hasher) = &mut hasher {
+                hasher.update(&chunk);
+            }
+            file.write_all(&chunk).await?;
+        }
+        anyhow::Ok(())
     }
+    .await;
 
+    // Keep what is downloaded so far for resuming, even if the connection was lost
     file.flush().await?;
+    res?;
+    if let Some(length) = length {
+        if downloaded != length {
+            return Err(TransientError::Incomplete {
+                received: downloaded,
+                expected: length,
+            }
+            .into());
+        }
+    }
+    file.sync_all().await?;
+    drop(file);
+
+    if let (Some(hasher), Some(expected)) = (hasher, expected_hash) {
+        if let Err(e) = hasher.verify(expected) {
+            tokio::fs::remove_file(&part).await?;
+            return Err(e);
+        }
+    }
 
-    pb.finish_with_message(format!("Downloaded {}", show_name));
+    tokio::fs::rename(&part, path).await?;
 
     Ok(())
 }
+
+/// First byte position of a `Content-Range: bytes <start>-<end>/<size>` header
+fn content_range_start(resp: &reqwest::Response) -> Option<u64> {
+    let range = resp.headers().get(header::CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+/// Hash of the contents of the file at `path`
+pub async fn hash_file(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<ManifestHash> {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update_from_file(path).await?;
+    Ok(hasher.finalize())
+}
+
+/// Hash of the downloaded file, calculated while downloading
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(sha2::Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+        }
+    }
+
+    /// Hash the contents of the file, e.g. the part that was downloaded before
+    async fn update_from_file(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut file = File::open(path).await?;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            self.update(&buf[..n]);
+        }
+    }
+
+    fn finalize(self) -> ManifestHash {
+        let (algorithm, digest) = match self {
+            Hasher::Md5(h) => (HashAlgorithm::Md5, h.finalize().to_vec()),
+            Hasher::Sha1(h) => (HashAlgorithm::Sha1, h.finalize().to_vec()),
+            Hasher::Sha256(h) => (HashAlgorithm::Sha256, h.finalize().to_vec()),
+            Hasher::Sha512(h) => (HashAlgorithm::Sha512, h.finalize().to_vec()),
+        };
+        ManifestHash::new(algorithm, digest)
+    }
+
+    fn verify(self, expected: ManifestHash) -> anyhow::Result<()> {
+        let actual = self.finalize();
+        if actual != expected {
+            anyhow::bail!(
+                "Hash mismatch\n\texpected: {}:{}\n\tactual:   {}:{}",
+                expected.algorithm.as_str(),
+                expected.hex(),
+                actual.algorithm.as_str(),
+                actual.hex()
+            );
+        }
+        Ok(())
+    }
+}
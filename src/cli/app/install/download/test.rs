@@ -0,0 +1,712 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    str::FromStr as _,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::StreamExt as _;
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::{
+    body::{Bytes, Frame, Incoming},
+    header, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use indicatif::ProgressBar;
+use interface::{
+    config::{Config, NetworkConfig},
+    manifest::{HashAlgorithm, Manifest, ManifestHash},
+};
+use sha2::Digest as _;
+use tokio::net::TcpListener;
+
+use super::{
+    client, download_to_cache, download_with_retry, is_cached, is_same_origin, part_path,
+    retry_after, DownloadRequest, RetryPolicy, TransientError, MAX_REDIRECTS,
+};
+
+type Body = BoxBody<Bytes, std::io::Error>;
+
+/// Contents of the file served by the test server
+fn content() -> Vec<u8> {
+    (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+}
+
+fn sha256(data: &[u8]) -> ManifestHash {
+    ManifestHash::new(HashAlgorithm::Sha256, sha2::Sha256::digest(data).to_vec())
+}
+
+fn full(data: Vec<u8>) -> Body {
+    Full::new(Bytes::from(data))
+        .map_err(|e: Infallible| match e {})
+        .boxed()
+}
+
+/// Body that sends `data` and then drops the connection
+fn dropped_after(data: Vec<u8>) -> Body {
+    let data = futures::stream::once(async { Ok(Frame::data(Bytes::from(data))) });
+    // Wait so that `data` is sent before the connection is dropped
+    let dropped = futures::stream::once(async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Err(std::io::Error::other("connection dropped"))
+    });
+    BodyExt::boxed(StreamBody::new(data.chain(dropped)))
+}
+
+/// Local server responding with `handler`. The `Range` headers of the requests are recorded.
+async fn serve(
+    handler: impl Fn(Option<&str>) -> Response<Body> + Send + Sync + 'static,
+) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+    serve_with_headers(move |headers| {
+        let range = headers.get(header::RANGE).map(|v| v.to_str().unwrap());
+        handler(range)
+    })
+    .await
+}
+
+/// Same as [`serve`], but `handler` gets all headers of the request
+async fn serve_with_headers(
+    handler: impl Fn(&header::HeaderMap) -> Response<Body> + Send + Sync + 'static,
+) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .await
+        .unwrap();
+    let url = format!("http://{}/app.zip", listener.local_addr().unwrap());
+    let ranges = Arc::new(Mutex::new(Vec::new()));
+    let handler = Arc::new(handler);
+
+    let recorded = ranges.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+            let recorded = recorded.clone();
+            let service = hyper::service::service_fn(move |req: Request<Incoming>| {
+                let range = req
+                    .headers()
+                    .get(header::RANGE)
+                    .map(|v| v.to_str().unwrap().to_string());
+                recorded.lock().unwrap().push(range);
+                let resp = handler(req.headers());
+                async move { Ok::<_, Infallible>(resp) }
+            });
+            tokio::spawn(
+                hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service),
+            );
+        }
+    });
+
+    (url, ranges)
+}
+
+/// Sends the whole file with `200`, or the rest of it with `206` for `Range: bytes=<start>-`.
+/// Without `Range`, the connection is dropped at the half of the file.
+fn range_handler(range: Option<&str>) -> Response<Body> {
+    let content = content();
+    let len = content.len();
+    match range {
+        None => Response::builder()
+            .header(header::CONTENT_LENGTH, len)
+            .body(dropped_after(content[..len / 2].to_vec()))
+            .unwrap(),
+        Some(range) => {
+            let start = range
+                .strip_prefix("bytes=")
+                .and_then(|r| r.strip_suffix('-'))
+                .and_then(|r| r.parse::<usize>().ok())
+                .unwrap();
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, len - 1, len),
+                )
+                .header(header::CONTENT_LENGTH, len - start)
+                .body(full(content[start..].to_vec()))
+                .unwrap()
+        }
+    }
+}
+
+fn request(url: &str) -> DownloadRequest {
+    DownloadRequest {
+        url: url.to_string(),
+        headers: header::HeaderMap::new(),
+        cookie: None,
+    }
+}
+
+async fn download(
+    url: &str,
+    path: &std::path::Path,
+    hash: Option<ManifestHash>,
+) -> anyhow::Result<()> {
+    download_to_cache(
+        &reqwest::Client::new(),
+        &request(url),
+        path,
+        hash,
+        &ProgressBar::hidden(),
+    )
+    .await
+}
+
+/// Retries without waiting long
+fn retry_policy(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        retries,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    }
+}
+
+async fn download_with_retries(
+    url: &str,
+    path: &std::path::Path,
+    retries: u32,
+) -> (u32, anyhow::Result<()>) {
+    download_with_retry(
+        &reqwest::Client::new(),
+        &request(url),
+        path,
+        "app",
+        None,
+        &ProgressBar::hidden(),
+        retry_policy(retries),
+    )
+    .await
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(full(Vec::new()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn resume_interrupted_download() {
+    let (url, ranges) = serve(range_handler).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    let content = content();
+
+    download(&url, &path, Some(sha256(&content)))
+        .await
+        .unwrap_err();
+    assert!(!path.exists());
+    let part = std::fs::read(part_path(&path)).unwrap();
+    assert_eq!(part, content[..content.len() / 2]);
+
+    // The hash covers the part downloaded before, too
+    download(&url, &path, Some(sha256(&content))).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+    assert!(!part_path(&path).exists());
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        [None, Some(format!("bytes={}-", content.len() / 2))]
+    );
+}
+
+#[tokio::test]
+async fn restart_if_range_is_ignored() {
+    let (url, _) = serve(|_| Response::new(full(content()))).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    std::fs::write(part_path(&path), b"stale").unwrap();
+
+    download(&url, &path, Some(sha256(&content())))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+}
+
+#[tokio::test]
+async fn restart_if_range_is_not_satisfiable() {
+    let (url, ranges) = serve(|range| match range {
+        Some(_) => Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .body(full(Vec::new()))
+            .unwrap(),
+        None => Response::new(full(content())),
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    std::fs::write(part_path(&path), content()).unwrap();
+
+    download(&url, &path, None).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+    assert_eq!(ranges.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn restart_if_range_is_unexpected() {
+    let (url, ranges) = serve(|range| match range {
+        Some(_) => {
+            let content = content();
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes 1-{}/{}", content.len() - 1, content.len()),
+                )
+                .body(full(content[1..].to_vec()))
+                .unwrap()
+        }
+        None => Response::new(full(content())),
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    std::fs::write(part_path(&path), b"stale").unwrap();
+
+    download(&url, &path, Some(sha256(&content())))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+    assert_eq!(ranges.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn hash_mismatch_removes_part() {
+    let (url, _) = serve(|_| Response::new(full(content()))).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let err = download(&url, &path, Some(sha256(b"other")))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Hash mismatch"), "{:#}", err);
+    assert!(!path.exists());
+    assert!(!part_path(&path).exists());
+}
+
+#[tokio::test]
+async fn retry_server_errors() {
+    let count = AtomicUsize::new(0);
+    let (url, _) = serve(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+        0 => status(StatusCode::SERVICE_UNAVAILABLE),
+        1 => status(StatusCode::TOO_MANY_REQUESTS),
+        _ => Response::new(full(content())),
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let (attempts, res) = download_with_retries(&url, &path, 3).await;
+    res.unwrap();
+    assert_eq!(attempts, 3);
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+}
+
+#[tokio::test]
+async fn retry_dropped_connection_resumes() {
+    let (url, ranges) = serve(range_handler).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let (attempts, res) = download_with_retries(&url, &path, 3).await;
+    res.unwrap();
+    assert_eq!(attempts, 2);
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+    assert_eq!(
+        ranges.lock().unwrap()[1],
+        Some(format!("bytes={}-", content().len() / 2))
+    );
+}
+
+#[tokio::test]
+async fn give_up_after_retries() {
+    let (url, ranges) = serve(|_| status(StatusCode::INTERNAL_SERVER_ERROR)).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let (attempts, res) = download_with_retries(&url, &path, 2).await;
+    assert!(res.unwrap_err().to_string().contains("500"));
+    assert_eq!(attempts, 3);
+    assert_eq!(ranges.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn no_retry_for_client_errors() {
+    let (url, ranges) = serve(|_| status(StatusCode::NOT_FOUND)).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let (attempts, res) = download_with_retries(&url, &path, 3).await;
+    assert!(res.is_err());
+    assert_eq!(attempts, 1);
+    assert_eq!(ranges.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn retry_delay() {
+    let policy = RetryPolicy {
+        retries: 5,
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(10),
+    };
+    let server_error = anyhow::Error::from(TransientError::Status {
+        status: StatusCode::BAD_GATEWAY,
+        retry_after: None,
+    });
+    assert_eq!(policy.delay(&server_error, 1), Some(Duration::from_secs(1)));
+    assert_eq!(policy.delay(&server_error, 3), Some(Duration::from_secs(4)));
+    assert_eq!(
+        policy.delay(&server_error, 5),
+        Some(Duration::from_secs(10))
+    );
+    assert_eq!(policy.delay(&server_error, 6), None);
+
+    let too_many_requests = anyhow::Error::from(TransientError::Status {
+        status: StatusCode::TOO_MANY_REQUESTS,
+        retry_after: Some(Duration::from_secs(7)),
+    });
+    assert_eq!(
+        policy.delay(&too_many_requests, 1),
+        Some(Duration::from_secs(7))
+    );
+
+    assert_eq!(policy.delay(&anyhow::anyhow!("Hash mismatch"), 1), None);
+}
+
+#[test]
+fn parse_retry_after() {
+    let mut headers = header::HeaderMap::new();
+    assert_eq!(retry_after(&headers), None);
+
+    headers.insert(header::RETRY_AFTER, "120".parse().unwrap());
+    assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+    // Dates in the past can be retried immediately
+    headers.insert(
+        header::RETRY_AFTER,
+        "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+    );
+    assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+}
+
+#[tokio::test]
+async fn cached_file_with_matching_hash() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    assert!(!is_cached(&path, None).await.unwrap());
+
+    std::fs::write(&path, content()).unwrap();
+    assert!(is_cached(&path, None).await.unwrap());
+    assert!(is_cached(&path, Some(&sha256(&content()))).await.unwrap());
+}
+
+#[tokio::test]
+async fn cached_file_with_other_hash_is_removed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    std::fs::write(&path, b"old").unwrap();
+
+    assert!(!is_cached(&path, Some(&sha256(&content()))).await.unwrap());
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn send_cookies_and_host_headers() {
+    let (url, _) = serve_with_headers(|headers| {
+        let header = |name| headers.get(name).map(|v| v.to_str().unwrap());
+        let expected = header(header::USER_AGENT).is_some_and(|ua| ua.starts_with("scoop-rs/"))
+            && header(header::COOKIE) == Some("oraclelicense=accept-securebackup-cookie")
+            && header(header::AUTHORIZATION) == Some("Bearer token");
+        if expected {
+            Response::new(full(content()))
+        } else {
+            status(StatusCode::FORBIDDEN)
+        }
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let manifest = Manifest::from_str(
+        r#"{
+            "version": "1.0",
+            "homepage": "https://example.com",
+            "license": "MIT",
+            "cookie": {"oraclelicense": "accept-securebackup-cookie"}
+        }"#,
+    )
+    .unwrap();
+    let config_path = tmp.path().join("config.json");
+    std::fs::write(
+        &config_path,
+        r#"{"headers": {"127.0.0.1": {"Authorization": "Bearer token"}}}"#,
+    )
+    .unwrap();
+    let config = Config::from_path(&config_path).await.unwrap();
+
+    let without_headers = DownloadRequest::new(&url, &manifest, &Config::default()).unwrap();
+    let err = download_to_cache(
+        &client(&config.network).unwrap(),
+        &without_headers,
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("403"), "{:#}", err);
+
+    let request = DownloadRequest::new(&url, &manifest, &config).unwrap();
+    download_to_cache(
+        &client(&config.network).unwrap(),
+        &request,
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+}
+
+#[tokio::test]
+async fn drop_host_headers_on_redirect_to_other_host() {
+    // `localhost` is another host than `127.0.0.1` for the headers
+    let (other_url, other_requests) = serve_with_headers(|headers| {
+        let expected = headers.get("x-token").is_none()
+            && headers
+                .get(header::COOKIE)
+                .is_some_and(|c| c == "license=accept");
+        if expected {
+            Response::new(full(content()))
+        } else {
+            status(StatusCode::FORBIDDEN)
+        }
+    })
+    .await;
+    let other_url = other_url.replace("127.0.0.1", "localhost");
+    let (url, _) = serve_with_headers(move |headers| {
+        if headers.get("x-token").is_some_and(|t| t == "secret") {
+            Response::builder()
+                .status(StatusCode::FOUND)
+                .header(header::LOCATION, &other_url)
+                .body(full(Vec::new()))
+                .unwrap()
+        } else {
+            status(StatusCode::FORBIDDEN)
+        }
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let mut request = request(&url);
+    request
+        .headers
+        .insert("x-token", header::HeaderValue::from_static("secret"));
+    request.cookie = Some(header::HeaderValue::from_static("license=accept"));
+    download_to_cache(
+        &client(&NetworkConfig::default()).unwrap(),
+        &request,
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+    assert_eq!(other_requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn drop_host_headers_on_redirect_to_other_port() {
+    let (other_url, other_requests) = serve_with_headers(|headers| match headers.get("x-token") {
+        None => Response::new(full(content())),
+        Some(_) => status(StatusCode::FORBIDDEN),
+    })
+    .await;
+    let (url, _) = serve_with_headers(move |_| {
+        Response::builder()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, &other_url)
+            .body(full(Vec::new()))
+            .unwrap()
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let mut request = request(&url);
+    request
+        .headers
+        .insert("x-token", header::HeaderValue::from_static("secret"));
+    download_to_cache(
+        &client(&NetworkConfig::default()).unwrap(),
+        &request,
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(other_requests.lock().unwrap().len(), 1);
+}
+
+/// Server that redirects `hops` times, then sends the file. Every other redirect is to the same
+/// origin, and the others switch between `127.0.0.1` and `localhost`.
+async fn serve_redirects(hops: usize) -> String {
+    let count = AtomicUsize::new(0);
+    let (url, _) = serve_with_headers(move |headers| {
+        let hop = count.fetch_add(1, Ordering::SeqCst);
+        if hop == hops {
+            return Response::new(full(content()));
+        }
+        let host = headers.get(header::HOST).unwrap().to_str().unwrap();
+        let other = if hop.is_multiple_of(2) {
+            host.to_string()
+        } else if let Some(port) = host.strip_prefix("127.0.0.1") {
+            format!("localhost{}", port)
+        } else {
+            host.replacen("localhost", "127.0.0.1", 1)
+        };
+        Response::builder()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, format!("http://{}/app.zip", other))
+            .body(full(Vec::new()))
+            .unwrap()
+    })
+    .await;
+    url
+}
+
+#[tokio::test]
+async fn follow_redirects_up_to_limit() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+    let client = client(&NetworkConfig::default()).unwrap();
+
+    let url = serve_redirects(MAX_REDIRECTS).await;
+    download_to_cache(&client, &request(&url), &path, None, &ProgressBar::hidden())
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+
+    // Redirects to the same origin and to other hosts count towards the same limit
+    std::fs::remove_file(&path).unwrap();
+    let url = serve_redirects(MAX_REDIRECTS + 1).await;
+    let err = download_to_cache(&client, &request(&url), &path, None, &ProgressBar::hidden())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Too many redirects"), "{:#}", err);
+}
+
+#[tokio::test]
+async fn keep_host_headers_on_redirect_to_same_origin() {
+    let count = AtomicUsize::new(0);
+    let (url, _) = serve_with_headers(move |headers| {
+        if headers.get("x-token").is_none_or(|t| t != "secret") {
+            return status(StatusCode::FORBIDDEN);
+        }
+        if count.fetch_add(1, Ordering::SeqCst) == 0 {
+            Response::builder()
+                .status(StatusCode::FOUND)
+                .header(header::LOCATION, "/dl/app.zip")
+                .body(full(Vec::new()))
+                .unwrap()
+        } else {
+            Response::new(full(content()))
+        }
+    })
+    .await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let mut request = request(&url);
+    request
+        .headers
+        .insert("x-token", header::HeaderValue::from_static("secret"));
+    download_to_cache(
+        &client(&NetworkConfig::default()).unwrap(),
+        &request,
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+}
+
+#[tokio::test]
+async fn redirect_without_location_fails() {
+    let (url, _) = serve(|_| status(StatusCode::NOT_MODIFIED)).await;
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    let err = download_to_cache(
+        &client(&NetworkConfig::default()).unwrap(),
+        &request(&url),
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("304"), "{:#}", err);
+    assert!(!path.exists());
+}
+
+#[test]
+fn same_origin_redirects() {
+    let url = |s| reqwest::Url::parse(s).unwrap();
+    let from = url("https://example.com/app.zip");
+    assert!(is_same_origin(
+        &from,
+        &url("https://example.com/dl/app.zip")
+    ));
+    assert!(is_same_origin(
+        &from,
+        &url("https://EXAMPLE.com:443/app.zip")
+    ));
+    // Headers must not be sent in cleartext, or to another server
+    assert!(!is_same_origin(&from, &url("http://example.com/app.zip")));
+    assert!(!is_same_origin(
+        &from,
+        &url("https://example.com:8443/app.zip")
+    ));
+    assert!(!is_same_origin(
+        &from,
+        &url("https://cdn.example.com/app.zip")
+    ));
+}
+
+#[tokio::test]
+async fn download_through_proxy() {
+    // The proxy gets requests for other hosts, which don't exist here
+    let (url, _) = serve_with_headers(|headers| match headers.get(header::HOST) {
+        Some(host) if host == "scoop-rs.invalid" => Response::new(full(content())),
+        _ => status(StatusCode::BAD_GATEWAY),
+    })
+    .await;
+    let network = NetworkConfig {
+        proxy: Some(url.trim_end_matches("/app.zip").to_string()),
+        no_proxy: vec!["other.invalid".to_string()],
+        ..Default::default()
+    };
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("app.zip");
+
+    download_to_cache(
+        &client(&network).unwrap(),
+        &request("http://scoop-rs.invalid/app.zip"),
+        &path,
+        None,
+        &ProgressBar::hidden(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content());
+}
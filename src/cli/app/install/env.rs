@@ -1,9 +1,184 @@
-use interface::{bucket_app::BucketApp, manifest::Manifest};
+use std::path::PathBuf;
 
+use interface::{
+    bucket_app::BucketApp, dir::PERSIST_DIR, installed_app::InstalledApp, manifest::Manifest,
+};
+
+#[cfg(test)]
+mod test;
+
+/// Add `env_add_path` entries to the user PATH
 pub async fn path(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+    let paths = env_paths(&app.name, manifest);
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let current = user_env::get("Path")?.unwrap_or_default();
+    user_env::set("Path", Some(&add_paths(&current, &paths)))
 }
 
+/// Remove `env_add_path` entries added by [`path`] from the user PATH
+pub async fn remove_path(app_name: &str, manifest: &Manifest) -> anyhow::Result<()> {
+    let paths = env_paths(app_name, manifest);
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let Some(current) = user_env::get("Path")? else {
+        return Ok(());
+    };
+    user_env::set("Path", Some(&remove_paths(&current, &paths)))
+}
+
+/// Set `env_set` variables to the user environment
 pub async fn set_env(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+    let Some(env_set) = manifest.architecture_current().env_set else {
+        return Ok(());
+    };
+    for (name, value) in env_set {
+        let value = match value {
+            Some(serde_json::Value::String(s)) => s,
+            Some(v) => v.to_string(),
+            None => continue,
+        };
+        let value = substitute(&value, &app.name, &manifest.version);
+        user_env::set(&name, Some(&value))?;
+    }
+    Ok(())
+}
+
+/// Remove `env_set` variables set by [`set_env`]
+pub async fn remove_env(manifest: &Manifest) -> anyhow::Result<()> {
+    let Some(env_set) = manifest.architecture_current().env_set else {
+        return Ok(());
+    };
+    for name in env_set.keys() {
+        user_env::set(name, None)?;
+    }
+    Ok(())
+}
+
+fn current_dir(app_name: &str) -> PathBuf {
+    InstalledApp::from_name(app_name).path().join("current")
+}
+
+/// Absolute paths of `env_add_path` entries. They are relative to `current`.
+fn env_paths(app_name: &str, manifest: &Manifest) -> Vec<String> {
+    let dir = current_dir(app_name);
+    manifest
+        .architecture_current()
+        .env_add_path
+        .unwrap_or_default()
+        .iter()
+        .map(|p| {
+            if p == "." {
+                dir.to_string_lossy().to_string()
+            } else {
+                dir.join(p).to_string_lossy().to_string()
+            }
+        })
+        .collect()
+}
+
+/// `paths` in front of the entries of the PATH `current`, which are moved to the front if they
+/// are already in it
+fn add_paths(current: &str, paths: &[String]) -> String {
+    let mut entries = paths.to_vec();
+    entries.extend(
+        split_path(current)
+            .into_iter()
+            .filter(|e| !paths.iter().any(|p| p.eq_ignore_ascii_case(e))),
+    );
+    entries.join(";")
+}
+
+/// The PATH `current` without `paths`. Paths are compared case-insensitively, as on Windows.
+fn remove_paths(current: &str, paths: &[String]) -> String {
+    split_path(current)
+        .into_iter()
+        .filter(|e| !paths.iter().any(|p| p.eq_ignore_ascii_case(e)))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split(';')
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_string())
+        .collect()
+}
+
+/// Replace variables that Scoop allows in `env_set`
+pub(super) fn substitute(value: &str, app_name: &str, version: &str) -> String {
+    let dir = current_dir(app_name);
+    value
+        .replace(
+            "$persist_dir",
+            &PERSIST_DIR.join(app_name).to_string_lossy(),
+        )
+        .replace("$original_dir", &dir.to_string_lossy())
+        .replace("$dir", &dir.to_string_lossy())
+        .replace("$version", version)
+}
+
+#[cfg(windows)]
+mod user_env {
+    use winreg::{
+        enums::{RegType, HKEY_CURRENT_USER, KEY_READ, KEY_WRITE},
+        RegKey, RegValue,
+    };
+
+    fn environment() -> std::io::Result<RegKey> {
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey_with_flags("Environment", KEY_READ | KEY_WRITE)
+    }
+
+    pub fn get(name: &str) -> anyhow::Result<Option<String>> {
+        match environment()?.get_value::<String, _>(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set or delete (if `value` is `None`) the user environment variable.
+    /// Values are written as `REG_EXPAND_SZ` so that `%VAR%` in PATH keeps working.
+    pub fn set(name: &str, value: Option<&str>) -> anyhow::Result<()> {
+        let key = environment()?;
+        match value {
+            Some(value) => {
+                let bytes = value
+                    .encode_utf16()
+                    .chain(std::iter::once(0))
+                    .flat_map(|c| c.to_le_bytes())
+                    .collect();
+                key.set_raw_value(
+                    name,
+                    &RegValue {
+                        bytes,
+                        vtype: RegType::REG_EXPAND_SZ,
+                    },
+                )?;
+            }
+            None => match key.delete_value(name) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// The user environment is only persisted on Windows (in the registry).
+#[cfg(not(windows))]
+mod user_env {
+    pub fn get(_name: &str) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    pub fn set(_name: &str, _value: Option<&str>) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
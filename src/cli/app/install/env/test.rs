@@ -0,0 +1,72 @@
+use std::str::FromStr as _;
+
+use interface::{dir::PERSIST_DIR, manifest::Manifest};
+
+use super::*;
+
+fn paths(paths: &[&str]) -> Vec<String> {
+    paths.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn add_paths_to_front() {
+    assert_eq!(
+        add_paths(r"C:\Windows;;C:\Tools", &paths(&[r"C:\app\bin"])),
+        r"C:\app\bin;C:\Windows;C:\Tools"
+    );
+    // Entries already in PATH are moved, not duplicated
+    assert_eq!(
+        add_paths(r"C:\Windows;c:\APP\bin", &paths(&[r"C:\app\bin"])),
+        r"C:\app\bin;C:\Windows"
+    );
+    assert_eq!(add_paths("", &paths(&[r"C:\app"])), r"C:\app");
+}
+
+#[test]
+fn remove_paths_case_insensitively() {
+    assert_eq!(
+        remove_paths(
+            r"C:\app\bin;C:\Windows;c:\APP;%USERPROFILE%\bin",
+            &paths(&[r"C:\app\bin", r"C:\app"])
+        ),
+        r"C:\Windows;%USERPROFILE%\bin"
+    );
+    assert_eq!(remove_paths(r"C:\app", &paths(&[r"C:\app"])), "");
+}
+
+#[test]
+fn env_paths_are_in_current() {
+    let manifest = Manifest::from_str(
+        r#"{
+            "version": "1.0",
+            "homepage": "https://example.com",
+            "license": "MIT",
+            "env_add_path": [".", "bin"]
+        }"#,
+    )
+    .unwrap();
+    let current = current_dir("app");
+    assert_eq!(
+        env_paths("app", &manifest),
+        [
+            current.to_string_lossy().to_string(),
+            current.join("bin").to_string_lossy().to_string()
+        ]
+    );
+}
+
+#[test]
+fn substitute_variables() {
+    let current = current_dir("app").to_string_lossy().to_string();
+    let persist = PERSIST_DIR.join("app").to_string_lossy().to_string();
+    assert_eq!(
+        substitute("$persist_dir/data", "app", "1.0"),
+        format!("{}/data", persist)
+    );
+    assert_eq!(
+        substitute("$dir;$original_dir", "app", "1.0"),
+        format!("{};{}", current, current)
+    );
+    assert_eq!(substitute("v$version", "app", "1.0"), "v1.0");
+    assert_eq!(substitute("plain", "app", "1.0"), "plain");
+}
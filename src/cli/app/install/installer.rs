@@ -1,17 +1,171 @@
-use interface::{bucket_app::BucketApp, manifest::Manifest};
+use std::path::Path;
 
+use anyhow::Context as _;
+use interface::{
+    bucket_app::BucketApp,
+    cache,
+    extract::{self, ArchiveFormat},
+    installed_app::{AppInstallInfo, InstalledApp},
+    manifest::{Architecture, Manifest, ManifestInstaller},
+};
+
+use super::run_script::run_script;
+
+#[cfg(test)]
+mod test;
+
+/// Extract the downloaded files into the version directory. Files that are not archives
+/// are copied. `extract_dir` and `extract_to` apply to the url with the same index.
+/// If the manifest has the deprecated `msi` field, files of unknown format are extracted as MSI.
+/// With `innosetup: true`, all files are extracted as InnoSetup installers, same as Scoop.
 pub async fn extract(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+    let arch_m = manifest.architecture_current();
+    let dir = InstalledApp::from_name(&app.name)
+        .path()
+        .join(&manifest.version);
+    let extract_dirs = arch_m.extract_dir.unwrap_or_default();
+    let extract_tos = manifest.extract_to.clone().unwrap_or_default();
+    let is_msi = arch_m.msi.is_some();
+    let is_innosetup = manifest.innosetup == Some(true);
+
+    for (i, url) in arch_m.url.unwrap_or_default().into_iter().enumerate() {
+        let file = cache::cache_path(&app.name, &manifest.version, &url);
+        let file_name = url.target_name();
+        let dest = match extract_tos.get(i) {
+            Some(extract_to) => extract::extract_to_path(&dir, extract_to)?,
+            None => dir.clone(),
+        };
+        let extract_dir = extract_dirs.get(i).cloned();
+        let format = if is_innosetup {
+            Some(ArchiveFormat::InnoSetup)
+        } else {
+            ArchiveFormat::detect(&file_name).or(is_msi.then_some(ArchiveFormat::Msi))
+        };
+
+        let name = file_name.clone();
+        tokio::task::spawn_blocking(move || {
+            extract::extract_as(&file, &name, format, &dest, extract_dir.as_deref())
+        })
+        .await?
+        .with_context(|| format!("Failed to extract {}", file_name))?;
+    }
+
+    Ok(())
 }
 
+/// Run the `installer` of the manifest. Same as Scoop, `file` is run from the version directory
+/// with `args` first, and removed afterwards unless `keep` is set. Without `file`, the file of the
+/// first url is run. `script` runs after that.
 pub async fn run_installer(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+    let arch_m = manifest.architecture_current();
+    let Some(installer) = &arch_m.installer else {
+        return Ok(());
+    };
+    let dir = InstalledApp::from_name(&app.name)
+        .path()
+        .join(&manifest.version);
+    let first_url = arch_m
+        .url
+        .as_ref()
+        .and_then(|urls| urls.first())
+        .map(|url| url.target_name());
+
+    run_installer_in(
+        &dir,
+        &app.name,
+        &manifest.version,
+        installer,
+        first_url.as_deref(),
+    )
+    .await
+    .context("Failed to run installer")
 }
 
+async fn run_installer_in(
+    dir: &Path,
+    app_name: &str,
+    version: &str,
+    installer: &ManifestInstaller,
+    first_url: Option<&str>,
+) -> anyhow::Result<()> {
+    if installer.file.is_some() || installer.args.is_some() {
+        let Some(file) = installer.file.as_deref().or(first_url) else {
+            anyhow::bail!("The installer has no `file` and the manifest has no url");
+        };
+        let args = installer
+            .args
+            .iter()
+            .flatten()
+            .map(|a| {
+                a.replace("$dir", &dir.to_string_lossy())
+                    .replace("$version", version)
+            })
+            .collect::<Vec<_>>();
+        let path = dir.join(file);
+        let status = tokio::process::Command::new(&path)
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .await
+            .with_context(|| format!("Failed to start {}", file))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", file, status);
+        }
+        if installer.keep != Some(true) {
+            tokio::fs::remove_file(&path)
+                .await
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+
+    if let Some(script) = &installer.script {
+        run_script(script, app_name, version).await?;
+    }
+
+    Ok(())
+}
+
+/// Install the app as a PowerShell module. This is not supported yet, so installing an app with
+/// `psmodule` fails, and what was installed before this step is removed again by `install_app`.
 pub async fn install_psmodule(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+    if manifest.psmodule.is_none() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "{} is a PowerShell module (`psmodule`), which is not supported yet",
+        app.name
+    )
 }
 
-pub async fn create_info(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+/// Save `install.json` and `manifest.json` to the version directory.
+/// Upgrade and uninstall rely on these, so they work even after the bucket is gone.
+pub async fn create_info(
+    app: &BucketApp<'_>,
+    manifest: &Manifest,
+    shims: &[String],
+) -> anyhow::Result<()> {
+    let dir = InstalledApp::from_name(&app.name)
+        .path()
+        .join(&manifest.version);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let info = AppInstallInfo {
+        bucket: app.bucket.clone(),
+        architecture: Architecture::current().as_str().to_string(),
+        shims: shims.to_vec(),
+    };
+    tokio::fs::write(
+        dir.join("install.json"),
+        serde_json::to_string_pretty(&info)?,
+    )
+    .await
+    .context("Failed to write install.json")?;
+    tokio::fs::write(
+        dir.join("manifest.json"),
+        serde_json::to_string_pretty(manifest)?,
+    )
+    .await
+    .context("Failed to write manifest.json")?;
+
+    Ok(())
 }
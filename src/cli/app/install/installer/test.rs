@@ -0,0 +1,105 @@
+use super::*;
+use crate::cli::app::install::run_script::POWERSHELL;
+
+fn installer(
+    file: Option<&str>,
+    args: &[&str],
+    keep: Option<bool>,
+    script: Option<Vec<String>>,
+) -> ManifestInstaller {
+    ManifestInstaller {
+        comment: None,
+        args: (!args.is_empty()).then(|| args.iter().map(|a| a.to_string()).collect()),
+        file: file.map(|f| f.to_string()),
+        keep,
+        script,
+    }
+}
+
+#[cfg(unix)]
+fn write_executable(path: &Path, content: &str) {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    std::fs::write(path, content).unwrap();
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn run_installer_file_with_args() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    write_executable(
+        &dir.join("setup.sh"),
+        "#!/bin/sh\necho \"$1\" > \"$2/installed.txt\"\n",
+    );
+
+    let installer = installer(Some("setup.sh"), &["$version", "$dir"], None, None);
+    run_installer_in(dir, "app", "1.0", &installer, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(dir.join("installed.txt")).unwrap(),
+        "1.0\n"
+    );
+    // Removed after it ran because `keep` is not set
+    assert!(!dir.join("setup.sh").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn run_file_of_first_url_and_keep_it() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    write_executable(
+        &dir.join("app-setup.sh"),
+        "#!/bin/sh\ntouch installed.txt\n",
+    );
+
+    let installer = installer(None, &["/S"], Some(true), None);
+    run_installer_in(dir, "app", "1.0", &installer, Some("app-setup.sh"))
+        .await
+        .unwrap();
+
+    assert!(dir.join("installed.txt").exists());
+    assert!(dir.join("app-setup.sh").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn fail_when_installer_fails() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    write_executable(&dir.join("setup.sh"), "#!/bin/sh\nexit 3\n");
+
+    let installer = installer(Some("setup.sh"), &[], None, None);
+    assert!(run_installer_in(dir, "app", "1.0", &installer, None)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn run_installer_script() {
+    if std::process::Command::new(POWERSHELL)
+        .arg("-Help")
+        .output()
+        .is_err()
+    {
+        println!("{} is not available, skipping", POWERSHELL);
+        return;
+    }
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("script.txt");
+
+    let script = vec![format!(
+        "Set-Content -NoNewline -Path '{}' -Value \"$app $version\"",
+        out.to_string_lossy()
+    )];
+    let installer = installer(None, &[], None, Some(script));
+    run_installer_in(tmp.path(), "app", "1.0", &installer, None)
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "app 1.0");
+}
@@ -1,5 +1,10 @@
-use interface::bucket_app::BucketApp;
+use anyhow::Context as _;
+use interface::{bucket_app::BucketApp, installed_app::InstalledApp};
 
+/// Switch `current` of the app to the newly installed `version`
 pub async fn link_to_current(app: &BucketApp<'_>, version: &str) -> anyhow::Result<()> {
-    todo!()
+    InstalledApp::from_name(&app.name)
+        .link_current(version)
+        .await
+        .context("Failed to switch the current version")
 }
@@ -1,5 +1,125 @@
-use interface::{bucket_app::BucketApp, manifest::Manifest};
+use std::path::{Path, PathBuf};
 
+use anyhow::Context as _;
+use interface::{
+    bucket_app::BucketApp,
+    dir::PERSIST_DIR,
+    installed_app::{remove_dir_link, InstalledApp},
+    manifest::{Manifest, Persist},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Keep `persist` entries of the app in [`PERSIST_DIR`]`/<app>` and link them into the version
+/// directory, so that user data survives upgrades and uninstalls. Same as Scoop:
+///
+/// - On the first install, the data in the app is moved to the persist directory. If the app
+///   has none, an empty file is created for names with an extension, and a directory otherwise.
+/// - If data is already persisted, it is linked instead of the data in the new version, which
+///   is kept with the `.original` suffix.
 pub async fn persist(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
+    let version_dir = InstalledApp::from_name(&app.name)
+        .path()
+        .join(&manifest.version);
+    let persist_dir = PERSIST_DIR.join(&app.name);
+
+    for entry in manifest.persist.iter().flatten() {
+        persist_entry(&version_dir, &persist_dir, entry)
+            .await
+            .with_context(|| format!("Failed to persist {}", entry.target))?;
+    }
+    Ok(())
+}
+
+async fn persist_entry(
+    version_dir: &Path,
+    persist_dir: &Path,
+    entry: &Persist,
+) -> anyhow::Result<()> {
+    let name = entry.target.trim_end_matches(['/', '\\']);
+    let source = version_dir.join(name);
+    let target = persist_dir.join(
+        entry
+            .name
+            .as_deref()
+            .map(|n| n.trim_end_matches(['/', '\\']))
+            .unwrap_or(name),
+    );
+
+    // Linked by an earlier install of the same version
+    if is_link(&source).await {
+        remove_link(&source).await?;
+    }
+
+    if tokio::fs::try_exists(&target).await? {
+        if tokio::fs::try_exists(&source).await? {
+            let original = PathBuf::from(format!("{}.original", source.display()));
+            remove_all(&original).await?;
+            tokio::fs::rename(&source, &original).await?;
+        }
+    } else {
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if tokio::fs::try_exists(&source).await? {
+            tokio::fs::rename(&source, &target).await?;
+        } else if Path::new(name).extension().is_some() {
+            tokio::fs::write(&target, "").await?;
+        } else {
+            tokio::fs::create_dir_all(&target).await?;
+        }
+    }
+
+    if let Some(parent) = source.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    link(&target, &source)
+        .await
+        .with_context(|| format!("Failed to link {}", source.display()))
+}
+
+/// Symlinks on Unix and junctions on Windows, which can be removed without touching the data
+async fn is_link(path: &Path) -> bool {
+    tokio::fs::symlink_metadata(path)
+        .await
+        .is_ok_and(|m| m.file_type().is_symlink())
+}
+
+async fn remove_link(path: &Path) -> anyhow::Result<()> {
+    if path.is_dir() {
+        remove_dir_link(path).await?;
+    } else {
+        tokio::fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
+async fn remove_all(path: &Path) -> anyhow::Result<()> {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(m) if m.is_dir() => tokio::fs::remove_dir_all(path).await?,
+        Ok(_) => tokio::fs::remove_file(path).await?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+/// Link `link` to the persisted `target`
+#[cfg(not(windows))]
+async fn link(target: &Path, link: &Path) -> anyhow::Result<()> {
+    tokio::fs::symlink(target, link).await?;
+    Ok(())
+}
+
+/// Link `link` to the persisted `target` with a junction for a directory and a hard link for a
+/// file, neither of which needs admin rights
+#[cfg(windows)]
+async fn link(target: &Path, link: &Path) -> anyhow::Result<()> {
+    if target.is_dir() {
+        interface::installed_app::create_dir_link(target, link)?;
+    } else {
+        tokio::fs::hard_link(target, link).await?;
+    }
+    Ok(())
 }
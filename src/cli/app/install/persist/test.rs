@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use interface::manifest::Persist;
+
+use super::*;
+
+fn entry(target: &str, name: Option<&str>) -> Persist {
+    Persist {
+        target: target.to_string(),
+        name: name.map(|n| n.to_string()),
+    }
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[tokio::test]
+async fn first_install_moves_or_creates_data() {
+    let tmp = tempfile::tempdir().unwrap();
+    let version_dir = tmp.path().join("apps/app/1.0");
+    let persist_dir = tmp.path().join("persist/app");
+    std::fs::create_dir_all(version_dir.join("data")).unwrap();
+    std::fs::write(version_dir.join("data/db.txt"), "default").unwrap();
+
+    for entry in [
+        entry("data\\", None),
+        entry("settings.json", None),
+        entry("cache", None),
+        entry("conf/app.ini", Some("app.ini")),
+    ] {
+        persist_entry(&version_dir, &persist_dir, &entry)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(read(&persist_dir.join("data/db.txt")), "default");
+    assert_eq!(read(&version_dir.join("data/db.txt")), "default");
+    assert!(persist_dir.join("settings.json").is_file());
+    assert!(persist_dir.join("cache").is_dir());
+    assert!(persist_dir.join("app.ini").is_file());
+    assert!(version_dir.join("conf/app.ini").is_file());
+
+    // Written through the link
+    std::fs::write(version_dir.join("data/db.txt"), "user data").unwrap();
+    assert_eq!(read(&persist_dir.join("data/db.txt")), "user data");
+}
+
+#[tokio::test]
+async fn upgrade_links_persisted_data() {
+    let tmp = tempfile::tempdir().unwrap();
+    let persist_dir = tmp.path().join("persist/app");
+    let old_dir = tmp.path().join("apps/app/1.0");
+    let new_dir = tmp.path().join("apps/app/2.0");
+    for dir in [&old_dir, &new_dir] {
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("data/db.txt"), "default").unwrap();
+        std::fs::write(dir.join("config.ini"), "default").unwrap();
+    }
+    let entries = [entry("data", None), entry("config.ini", None)];
+
+    for entry in &entries {
+        persist_entry(&old_dir, &persist_dir, entry).await.unwrap();
+    }
+    std::fs::write(old_dir.join("data/db.txt"), "user data").unwrap();
+    std::fs::write(persist_dir.join("config.ini"), "user config").unwrap();
+
+    for entry in &entries {
+        persist_entry(&new_dir, &persist_dir, entry).await.unwrap();
+    }
+    assert_eq!(read(&new_dir.join("data/db.txt")), "user data");
+    assert_eq!(read(&new_dir.join("config.ini")), "user config");
+    assert_eq!(read(&new_dir.join("data.original/db.txt")), "default");
+    assert_eq!(read(&new_dir.join("config.ini.original")), "default");
+
+    // Installing the same version again, and removing the old one, keeps the data
+    for entry in &entries {
+        persist_entry(&new_dir, &persist_dir, entry).await.unwrap();
+    }
+    std::fs::remove_dir_all(&old_dir).unwrap();
+    assert_eq!(read(&persist_dir.join("data/db.txt")), "user data");
+    assert_eq!(read(&new_dir.join("data/db.txt")), "user data");
+}
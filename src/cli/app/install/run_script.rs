@@ -1,4 +1,58 @@
+use anyhow::Context as _;
+use interface::{dir::PERSIST_DIR, installed_app::InstalledApp, manifest::Architecture};
+
+#[cfg(windows)]
+pub(super) const POWERSHELL: &str = "powershell";
+#[cfg(not(windows))]
+pub(super) const POWERSHELL: &str = "pwsh";
+
+#[cfg(test)]
+mod test;
+
 /// Run ps1 script
-pub async fn run_script(script: &Vec<String>) -> anyhow::Result<()> {
-    todo!()
+/// Variables that Scoop provides to manifest scripts (`$app`, `$version`, `$dir` ...) are defined
+/// before the script.
+pub async fn run_script(script: &[String], app: &str, version: &str) -> anyhow::Result<()> {
+    let command = script_command(script, app, version);
+
+    let status = tokio::process::Command::new(POWERSHELL)
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-ExecutionPolicy",
+            "Bypass",
+            "-Command",
+            &command,
+        ])
+        .status()
+        .await
+        .with_context(|| format!("Failed to start {}", POWERSHELL))?;
+
+    if !status.success() {
+        anyhow::bail!("Script exited with {}", status);
+    }
+
+    Ok(())
+}
+
+/// `script` with the variables of Scoop defined before it
+fn script_command(script: &[String], app: &str, version: &str) -> String {
+    let dir = InstalledApp::from_name(app).path().join(version);
+    let persist_dir = PERSIST_DIR.join(app);
+
+    let mut command = format!(
+        "$app = {}\n$version = {}\n$dir = {}\n$original_dir = $dir\n$persist_dir = {}\n$global = $false\n$architecture = {}\n",
+        quote(app),
+        quote(version),
+        quote(&dir.to_string_lossy()),
+        quote(&persist_dir.to_string_lossy()),
+        quote(Architecture::current().as_str()),
+    );
+    command.push_str(&script.join("\n"));
+    command
+}
+
+/// Quote string as powershell single-quoted string
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
 }
@@ -0,0 +1,22 @@
+use super::*;
+
+#[test]
+fn quote_single_quotes() {
+    assert_eq!(quote("app"), "'app'");
+    assert_eq!(quote("it's $dir"), "'it''s $dir'");
+}
+
+#[test]
+fn define_variables_before_script() {
+    let script = ["Write-Host $app".to_string(), "exit 0".to_string()];
+    let command = script_command(&script, "app", "1.0");
+    let dir = InstalledApp::from_name("app").path().join("1.0");
+
+    assert!(command.starts_with("$app = 'app'\n$version = '1.0'\n"));
+    assert!(command.contains(&format!("$dir = {}\n", quote(&dir.to_string_lossy()))));
+    assert!(command.contains(&format!(
+        "$architecture = '{}'\n",
+        Architecture::current().as_str()
+    )));
+    assert!(command.ends_with("\nWrite-Host $app\nexit 0"));
+}
@@ -0,0 +1,332 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use interface::{
+    bucket_app::BucketApp,
+    dir::SHIMS_DIR,
+    installed_app::{installed_apps, InstalledApp},
+    manifest::{Bin, Manifest},
+    shim::{self, Shim},
+};
+
+use super::env::substitute;
+
+#[cfg(test)]
+mod test;
+
+/// Create shims of `bin` entries in [`SHIMS_DIR`]. They point into `current`, so they keep
+/// working after an upgrade. Returns the names of the shims, to be recorded in `install.json`.
+pub async fn create_shims(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for bin in manifest.architecture_current().bin.unwrap_or_default() {
+        let owner = Shim::read(&bin.name).await?.and_then(|s| s.owner());
+        if let Some(owner) = owner.filter(|owner| *owner != app.name) {
+            println!(
+                "{}Shim `{}` of {} is replaced by the one of {}",
+                console::style("Warning: ").yellow(),
+                bin.name,
+                owner,
+                app.name
+            );
+        }
+        let shim = create_shim(&app.name, &manifest.version, &bin).await?;
+        names.push(shim.name);
+    }
+    Ok(names)
+}
+
+/// Create the shim of `bin` of the app in [`SHIMS_DIR`], replacing an existing one
+pub async fn create_shim(app_name: &str, version: &str, bin: &Bin) -> anyhow::Result<Shim> {
+    let app_dir = InstalledApp::from_name(app_name).path();
+    let path = resolve_target(
+        &app_dir.join(version),
+        &app_dir.join("current"),
+        &bin.target,
+    )
+    .with_context(|| format!("Can't shim `{}`: the file doesn't exist", bin.target))?;
+    let shim = Shim {
+        name: bin.name.clone(),
+        path,
+        args: shim_args(bin, app_name, version),
+    };
+    write_shim(&SHIMS_DIR, &shim)
+        .await
+        .with_context(|| format!("Failed to create shim `{}`", shim.name))?;
+    Ok(shim)
+}
+
+/// Remove the shims `names` of the app. Shims pointing into the app are removed. Shims whose
+/// target is outside of all apps (e.g. a command on PATH) can't be told apart from the ones of
+/// other apps, so they are only removed if they are in `recorded`, the shims the app recorded in
+/// `install.json`, and no other app recorded them.
+pub async fn remove_shims(
+    app_name: &str,
+    names: &[String],
+    recorded: &[String],
+) -> anyhow::Result<()> {
+    let mut recorded_by_others = None;
+    for name in names {
+        let owner = Shim::read(name).await?.and_then(|s| s.owner());
+        if owner.is_none() && recorded_by_others.is_none() {
+            recorded_by_others = Some(recorded_by_other_apps(app_name).await?);
+        }
+        let others = recorded_by_others.as_ref();
+        if is_removable(owner.as_deref(), app_name, name, recorded, others) {
+            remove_shim(name).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the shim `name` with `owner` is removed by [`remove_shims`]
+fn is_removable(
+    owner: Option<&str>,
+    app_name: &str,
+    name: &str,
+    recorded: &[String],
+    recorded_by_others: Option<&HashSet<String>>,
+) -> bool {
+    match owner {
+        Some(owner) => owner == app_name,
+        None => {
+            recorded.iter().any(|r| r == name)
+                && recorded_by_others.is_some_and(|others| !others.contains(name))
+        }
+    }
+}
+
+/// Shims recorded by the current versions of the installed apps other than `app_name`
+async fn recorded_by_other_apps(app_name: &str) -> anyhow::Result<HashSet<String>> {
+    let mut recorded = HashSet::new();
+    for app in installed_apps().await? {
+        if app.name == app_name {
+            continue;
+        }
+        let Ok(version) = app.current_version().await else {
+            continue;
+        };
+        if let Ok(info) = version.install_info().await {
+            recorded.extend(info.shims);
+        }
+    }
+    Ok(recorded)
+}
+
+/// Remove all files of the shim `name`. Returns false if there were none.
+pub async fn remove_shim(name: &str) -> anyhow::Result<bool> {
+    let files = shim::files_in(&SHIMS_DIR, name).await?;
+    for file in &files {
+        tokio::fs::remove_file(file)
+            .await
+            .with_context(|| format!("Failed to remove {}", file.display()))?;
+    }
+    Ok(!files.is_empty())
+}
+
+/// Path the shim of `target` runs, resolved the same way as Scoop: a file in the app, an
+/// absolute path, or a command in PATH
+fn resolve_target(version_dir: &Path, current_dir: &Path, target: &str) -> Option<PathBuf> {
+    if version_dir.join(target).is_file() {
+        return Some(current_dir.join(target));
+    }
+    let path = Path::new(target);
+    if path.is_absolute() && path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    find_in_path(target)
+}
+
+/// Search PATH for the command `name`, trying PATHEXT on Windows
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let extensions = if cfg!(windows) && Path::new(name).extension().is_none() {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(|ext| ext.to_string())
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", name, ext)))
+            .find(|path| path.is_file())
+    })
+}
+
+/// `args` of `bin` with Scoop variables replaced, quoted as on a command line
+fn shim_args(bin: &Bin, app_name: &str, version: &str) -> Option<String> {
+    let args = bin
+        .args
+        .iter()
+        .flatten()
+        .map(|arg| {
+            let arg = substitute(arg, app_name, version);
+            if arg.contains(char::is_whitespace) && !arg.starts_with('"') {
+                format!("\"{}\"", arg)
+            } else {
+                arg
+            }
+        })
+        .collect::<Vec<_>>();
+    (!args.is_empty()).then(|| args.join(" "))
+}
+
+/// Write the `.shim` file and the launchers of `shim` to `dir`. Files of an existing shim with
+/// the same name are removed first, as its target may have been of another kind.
+async fn write_shim(dir: &Path, shim: &Shim) -> anyhow::Result<()> {
+    for file in shim::files_in(dir, &shim.name).await? {
+        tokio::fs::remove_file(&file).await?;
+    }
+    tokio::fs::write(dir.join(format!("{}.shim", shim.name)), shim.to_shim_file()).await?;
+    write_launchers(dir, shim).await
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Program that runs scripts with the extension `ext`, which can't run by themselves
+fn interpreter(ext: &str) -> Option<&'static str> {
+    match ext {
+        "ps1" if cfg!(windows) => Some("powershell -NoProfile -ExecutionPolicy Bypass -File"),
+        "ps1" => Some("pwsh -NoProfile -File"),
+        "jar" => Some("java -jar"),
+        "py" if cfg!(windows) => Some("python"),
+        "py" => Some("python3"),
+        _ => None,
+    }
+}
+
+/// Executables get a copy of the shim executable of Scoop, and scripts get `.cmd` (and `.ps1`)
+/// wrappers. Without the shim executable, executables get a `.cmd` wrapper too.
+#[cfg(windows)]
+async fn write_launchers(dir: &Path, shim: &Shim) -> anyhow::Result<()> {
+    let path = shim.path.display();
+    let args = shim
+        .args
+        .as_deref()
+        .map(|args| format!(" {}", args))
+        .unwrap_or_default();
+    let ext = extension(&shim.path);
+
+    if ext == "exe" || ext == "com" {
+        match shim_exe() {
+            Ok(shim_exe) => {
+                tokio::fs::copy(shim_exe, dir.join(format!("{}.exe", shim.name))).await?;
+                return Ok(());
+            }
+            // The shim still works from cmd and PowerShell, but not where only `.exe` files are
+            // run, e.g. by other programs that start it
+            Err(e) => println!(
+                "{}{}, shim `{}` is created as a .cmd file instead",
+                console::style("Warning: ").yellow(),
+                e,
+                shim.name
+            ),
+        }
+    }
+
+    let command = match interpreter(&ext) {
+        Some(interpreter) => format!("{} \"{}\"", interpreter, path),
+        None => format!("\"{}\"", path),
+    };
+    tokio::fs::write(
+        dir.join(format!("{}.cmd", shim.name)),
+        format!("@rem {}\r\n@{}{} %*\r\n", path, command, args),
+    )
+    .await?;
+    if ext == "ps1" {
+        // Runs in the same PowerShell session when called from PowerShell
+        tokio::fs::write(
+            dir.join(format!("{}.ps1", shim.name)),
+            format!(
+                "# {}\r\n& \"{}\"{} @args\r\nexit $LASTEXITCODE\r\n",
+                path, path, args
+            ),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Shim executable of Scoop, which runs the target in the `.shim` file next to its copy
+#[cfg(windows)]
+fn shim_exe() -> anyhow::Result<PathBuf> {
+    let next_to_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join("shim.exe")));
+    let scoop =
+        interface::dir::INSTALL_DIR.join("apps/scoop/current/supporting/shims/kiennq/shim.exe");
+    [next_to_exe, Some(scoop)]
+        .into_iter()
+        .flatten()
+        .find(|path| path.is_file())
+        .context("shim.exe is found neither next to scoop-rs nor in Scoop")
+}
+
+/// Split `args` of a `.shim` file into arguments, which are separated by whitespace outside of
+/// double quotes as on Windows
+#[cfg(not(windows))]
+fn split_args(args: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut arg = None::<String>;
+    let mut quoted = false;
+    for c in args.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                arg.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => split.extend(arg.take()),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    split.extend(arg);
+    split
+}
+
+/// A `sh` script that runs the target
+#[cfg(not(windows))]
+async fn write_launchers(dir: &Path, shim: &Shim) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    // Inside double quotes, only these are special to `sh`
+    let path = shim
+        .path
+        .display()
+        .to_string()
+        .chars()
+        .fold(String::new(), |mut escaped, c| {
+            if matches!(c, '\\' | '"' | '$' | '`') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        });
+    let command = match interpreter(&extension(&shim.path)) {
+        Some(interpreter) => format!("{} \"{}\"", interpreter, path),
+        None => format!("\"{}\"", path),
+    };
+    // Nothing in single quotes is special to `sh`, so each argument is passed as it is
+    let args = split_args(shim.args.as_deref().unwrap_or_default())
+        .iter()
+        .map(|arg| format!(" '{}'", arg.replace('\'', r"'\''")))
+        .collect::<String>();
+
+    let file = dir.join(&shim.name);
+    tokio::fs::write(
+        &file,
+        format!("#!/bin/sh\nexec {}{} \"$@\"\n", command, args),
+    )
+    .await?;
+    tokio::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).await?;
+    Ok(())
+}
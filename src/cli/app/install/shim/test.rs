@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use interface::{manifest::Bin, shim::Shim};
+
+use super::*;
+
+fn bin(args: &[&str]) -> Bin {
+    Bin {
+        target: "app.exe".to_string(),
+        name: "app".to_string(),
+        args: Some(args.iter().map(|a| a.to_string()).collect()),
+    }
+}
+
+#[test]
+fn quote_args_with_spaces() {
+    assert_eq!(
+        shim_args(&bin(&["--flag", "a b", "\"quoted arg\""]), "app", "1.0"),
+        Some("--flag \"a b\" \"quoted arg\"".to_string())
+    );
+    assert_eq!(shim_args(&bin(&[]), "app", "1.0"), None);
+    assert_eq!(
+        shim_args(&bin(&["--version=$version"]), "app", "1.0"),
+        Some("--version=1.0".to_string())
+    );
+}
+
+#[test]
+fn resolve_targets() {
+    let tmp = tempfile::tempdir().unwrap();
+    let version_dir = tmp.path().join("1.0");
+    let current_dir = tmp.path().join("current");
+    std::fs::create_dir_all(version_dir.join("bin")).unwrap();
+    std::fs::write(version_dir.join("bin").join("app.exe"), "").unwrap();
+
+    // Files of the app are run through `current`, so that shims survive upgrades
+    assert_eq!(
+        resolve_target(&version_dir, &current_dir, "bin/app.exe"),
+        Some(current_dir.join("bin/app.exe"))
+    );
+    let absolute = version_dir.join("bin").join("app.exe");
+    assert_eq!(
+        resolve_target(&version_dir, &current_dir, absolute.to_str().unwrap()),
+        Some(absolute)
+    );
+    assert_eq!(
+        resolve_target(&version_dir, &current_dir, "missing.exe"),
+        None
+    );
+}
+
+fn shim(path: &Path, args: Option<&str>) -> Shim {
+    Shim {
+        name: "app".to_string(),
+        path: path.to_path_buf(),
+        args: args.map(|a| a.to_string()),
+    }
+}
+
+#[tokio::test]
+async fn write_replaces_existing_shim() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("app.cmd"), "old").unwrap();
+    std::fs::write(tmp.path().join("app.ps1"), "old").unwrap();
+
+    let shim = shim(&PathBuf::from("/apps/app/current/app"), Some("--flag"));
+    write_shim(tmp.path(), &shim).await.unwrap();
+
+    assert_eq!(Shim::read_in(tmp.path(), "app").await.unwrap(), Some(shim));
+    assert!(!tmp.path().join("app.ps1").exists());
+}
+
+#[cfg(not(windows))]
+#[tokio::test]
+async fn run_shim_with_args() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("dir with $pecial \"chars\"").join("app");
+    std::fs::create_dir(target.parent().unwrap()).unwrap();
+    std::fs::write(&target, "#!/bin/sh\nprintf '%s\\n' \"$@\"\n").unwrap();
+    std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let shims_dir = tmp.path().join("shims");
+    std::fs::create_dir(&shims_dir).unwrap();
+    write_shim(
+        &shims_dir,
+        &shim(&target, Some("--fixed \"a b\" \"\" $HOME;`id`'q'")),
+    )
+    .await
+    .unwrap();
+
+    let output = tokio::process::Command::new(shims_dir.join("app"))
+        .args(["extra", "c d"])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "--fixed\na b\n\n$HOME;`id`'q'\nextra\nc d\n"
+    );
+}
+
+#[test]
+fn remove_only_shims_of_the_app() {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let recorded = names(&["app", "tool"]);
+    let others = HashSet::from(["shared".to_string()]);
+    let removable = |owner, name: &str| is_removable(owner, "app", name, &recorded, Some(&others));
+
+    assert!(removable(Some("app"), "app"));
+    assert!(!removable(Some("other"), "app"));
+    // Shims with targets outside of the apps, e.g. on PATH
+    assert!(removable(None, "tool"));
+    assert!(!removable(None, "unrecorded"));
+    assert!(!is_removable(
+        None,
+        "app",
+        "shared",
+        &names(&["shared"]),
+        Some(&others)
+    ));
+}
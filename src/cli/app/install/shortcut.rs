@@ -1,12 +1,101 @@
-use interface::{bucket_app::BucketApp, manifest::Manifest};
+use std::path::Path;
 
-pub async fn create_shims(app: &BucketApp<'_>, manifest: &Manifest) -> anyhow::Result<()> {
-    todo!()
-}
+use anyhow::Context as _;
+use interface::{
+    bucket_app::BucketApp,
+    installed_app::InstalledApp,
+    lnk::ShellLink,
+    manifest::{Bin, Manifest},
+};
+
+use super::env::substitute;
+use crate::cli::load_config;
+
+#[cfg(test)]
+mod test;
 
+/// Create start menu shortcuts of `shortcuts` entries. Like shims, they point into `current`.
 pub async fn create_startmenu_shortcuts(
     app: &BucketApp<'_>,
     manifest: &Manifest,
 ) -> anyhow::Result<()> {
-    todo!()
+    let dir = load_config().await?.shortcuts_dir();
+    let app_dir = InstalledApp::from_name(&app.name).path();
+    let version_dir = app_dir.join(&manifest.version);
+    let current_dir = app_dir.join("current");
+
+    for shortcut in manifest
+        .architecture_current()
+        .shortcuts
+        .unwrap_or_default()
+    {
+        let mut link = shell_link(&version_dir, &current_dir, &shortcut)?;
+        link.arguments = link
+            .arguments
+            .map(|args| substitute(&args, &app.name, &manifest.version));
+
+        let path = dir.join(format!("{}.lnk", shortcut.name));
+        // The name can contain a folder, like `Tools\App`
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, link.to_bytes())
+            .await
+            .with_context(|| format!("Failed to create shortcut {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Link for a `shortcuts` entry of the app installed in `version_dir`. Entries are
+/// `[target, name, args, icon]`, so `args` holds both the arguments and the icon.
+fn shell_link(version_dir: &Path, current_dir: &Path, shortcut: &Bin) -> anyhow::Result<ShellLink> {
+    if !version_dir.join(&shortcut.target).is_file() {
+        anyhow::bail!(
+            "Can't create shortcut `{}`: {} doesn't exist",
+            shortcut.name,
+            shortcut.target
+        );
+    }
+    let target = current_dir.join(&shortcut.target);
+    let args = shortcut.args.as_deref().unwrap_or_default();
+
+    let mut link = ShellLink::new(&target);
+    link.working_dir = target.parent().map(Path::to_path_buf);
+    link.arguments = args.first().filter(|args| !args.is_empty()).cloned();
+    // Same as Scoop, a missing icon is ignored
+    link.icon = args
+        .get(1)
+        .filter(|icon| version_dir.join(icon).is_file())
+        .map(|icon| current_dir.join(icon));
+    Ok(link)
+}
+
+/// Remove start menu shortcuts of `shortcuts` entries
+pub async fn remove_startmenu_shortcuts(manifest: &Manifest) -> anyhow::Result<()> {
+    let dir = load_config().await?.shortcuts_dir();
+    for shortcut in manifest
+        .architecture_current()
+        .shortcuts
+        .unwrap_or_default()
+    {
+        let path = dir.join(format!("{}.lnk", shortcut.name));
+        remove_if_exists(&path).await?;
+        // Folders in the name are removed once they are empty
+        for parent in path.ancestors().skip(1).take_while(|p| *p != dir) {
+            if tokio::fs::remove_dir(parent).await.is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn remove_if_exists(path: &Path) -> anyhow::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => {
+            Err(anyhow::Error::new(e).context(format!("Failed to remove {}", path.display())))
+        }
+    }
 }
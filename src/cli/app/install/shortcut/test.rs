@@ -0,0 +1,47 @@
+use interface::manifest::Bin;
+
+use super::*;
+
+fn shortcut(target: &str, args: &[&str]) -> Bin {
+    Bin {
+        target: target.to_string(),
+        name: "App".to_string(),
+        args: (!args.is_empty()).then(|| args.iter().map(|a| a.to_string()).collect()),
+    }
+}
+
+#[test]
+fn shortcut_points_into_current() {
+    let tmp = tempfile::tempdir().unwrap();
+    let version_dir = tmp.path().join("1.0");
+    let current_dir = tmp.path().join("current");
+    std::fs::create_dir_all(version_dir.join("bin")).unwrap();
+    std::fs::write(version_dir.join("bin").join("app.exe"), "").unwrap();
+    std::fs::write(version_dir.join("app.ico"), "").unwrap();
+
+    let link = shell_link(
+        &version_dir,
+        &current_dir,
+        &shortcut("bin/app.exe", &["--flag", "app.ico"]),
+    )
+    .unwrap();
+    assert_eq!(link.target, current_dir.join("bin/app.exe"));
+    assert_eq!(
+        link.working_dir.as_deref(),
+        Some(current_dir.join("bin").as_path())
+    );
+    assert_eq!(link.arguments.as_deref(), Some("--flag"));
+    assert_eq!(link.icon, Some(current_dir.join("app.ico")));
+
+    // Scoop manifests use an empty string for an icon without arguments
+    let link = shell_link(
+        &version_dir,
+        &current_dir,
+        &shortcut("bin/app.exe", &["", "missing.ico"]),
+    )
+    .unwrap();
+    assert_eq!(link.arguments, None);
+    assert_eq!(link.icon, None);
+
+    assert!(shell_link(&version_dir, &current_dir, &shortcut("missing.exe", &[])).is_err());
+}
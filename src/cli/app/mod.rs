@@ -4,6 +4,7 @@ use super::CliResult;
 
 pub mod install;
 mod list;
+pub mod reset;
 pub mod search;
 pub mod uninstall;
 pub mod upgrade;
@@ -34,6 +35,9 @@ enum AppCommand {
 
     /// Show list of installed apps
     List,
+
+    /// Switch the current version of an app to another installed version
+    Reset(reset::ResetArgs),
 }
 
 pub async fn start(opts: AppArgs) -> CliResult {
@@ -43,5 +47,6 @@ pub async fn start(opts: AppArgs) -> CliResult {
         AppCommand::Upgrade(args) => upgrade::start(args).await,
         AppCommand::Search(args) => search::start(args).await,
         AppCommand::List => list::start().await,
+        AppCommand::Reset(args) => reset::start(args).await,
     }
 }
@@ -0,0 +1,62 @@
+use anyhow::Context as _;
+use clap::Args;
+use interface::installed_app::InstalledApp;
+
+use crate::cli::CliResult;
+
+#[derive(Debug, Args)]
+pub struct ResetArgs {
+    /// App and the installed version to switch to, as `<app>@<version>`
+    pub app: String,
+}
+
+pub async fn start(opts: ResetArgs) -> CliResult {
+    start_inner(opts).await.map_err(|e| format!("{:#}", e))
+}
+
+async fn start_inner(opts: ResetArgs) -> anyhow::Result<()> {
+    let Some((name, version)) = opts.app.split_once('@') else {
+        anyhow::bail!("Specify the version to switch to as `<app>@<version>`");
+    };
+    let app = InstalledApp::from_name(name);
+    if !app.is_installed().await {
+        anyhow::bail!("`{}` is not installed", name);
+    }
+
+    let versions = app.versions().await.context("Failed to get versions")?;
+    let Some(target) = versions.iter().find(|v| v.version == version) else {
+        anyhow::bail!(
+            "Version {} of {} is not installed. Installed versions: {}",
+            version,
+            name,
+            versions
+                .iter()
+                .map(|v| v.version.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+    // `install.json` is written last, so a version without it was not installed completely
+    if target.install_info().await.is_err() {
+        anyhow::bail!(
+            "Version {} of {} is not installed completely",
+            version,
+            name
+        );
+    }
+
+    let current = app.current_version().await.ok().map(|v| v.version);
+    if current.as_deref() == Some(version) {
+        println!("{} is already at {}", name, version);
+        return Ok(());
+    }
+
+    app.link_current(version).await?;
+    println!(
+        "Switched {} from {} to {}",
+        name,
+        current.as_deref().unwrap_or("none"),
+        version
+    );
+    Ok(())
+}
@@ -1,13 +1,142 @@
+use anyhow::Context as _;
 use clap::Args;
+use interface::{
+    dir::PERSIST_DIR,
+    installed_app::{AppVersion, InstalledApp},
+    manifest::Uninstaller,
+};
 
 use crate::cli::CliResult;
 
+use super::install::{env, run_script::run_script, shim, shortcut};
+
 #[derive(Debug, Args)]
 pub struct UninstallArgs {
     pub name: String,
+    /// Also remove persisted data of the app
+    #[clap(long, default_value_t = false)]
+    pub purge: bool,
 }
 
 pub async fn start(opts: UninstallArgs) -> CliResult {
+    start_inner(opts).await.map_err(|e| format!("{:#}", e))
+}
+
+async fn start_inner(opts: UninstallArgs) -> anyhow::Result<()> {
+    let app = InstalledApp::from_name(&opts.name);
+    if !app.is_installed().await {
+        anyhow::bail!("`{}` is not installed", opts.name);
+    }
+
     println!("Uninstalling {}", opts.name);
+
+    // The manifest saved on install is used, so this works even if the bucket was removed.
+    let version = app
+        .current_version()
+        .await
+        .context("Failed to get current version")?;
+    let manifest = version
+        .manifest()
+        .await
+        .context("Failed to get installed manifest")?;
+    let arch_m = manifest.architecture_current();
+
+    if let Some(pre_uninstall) = &arch_m.pre_uninstall {
+        run_script(pre_uninstall, &app.name, &version.version)
+            .await
+            .context("Failed to run pre-uninstall script")?;
+    }
+
+    if let Some(uninstaller) = &arch_m.uninstaller {
+        run_uninstaller(&app, &version, uninstaller)
+            .await
+            .context("Failed to run uninstaller")?;
+    }
+
+    // Apps installed before shims were recorded have shims named after their `bin` entries
+    let recorded = version
+        .install_info()
+        .await
+        .map(|info| info.shims)
+        .unwrap_or_default();
+    let shims = if recorded.is_empty() {
+        arch_m
+            .bin
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|bin| bin.name)
+            .collect()
+    } else {
+        recorded.clone()
+    };
+    shim::remove_shims(&app.name, &shims, &recorded)
+        .await
+        .context("Failed to remove shims")?;
+    shortcut::remove_startmenu_shortcuts(&manifest)
+        .await
+        .context("Failed to remove shortcuts")?;
+
+    env::remove_path(&app.name, &manifest)
+        .await
+        .context("Failed to remove PATH entries")?;
+    env::remove_env(&manifest)
+        .await
+        .context("Failed to remove environment variables")?;
+
+    // Same as Scoop, `post_uninstall` runs before the app directory is removed so that it can
+    // still use files in `$dir`.
+    if let Some(post_uninstall) = &arch_m.post_uninstall {
+        run_script(post_uninstall, &app.name, &version.version)
+            .await
+            .context("Failed to run post-uninstall script")?;
+    }
+
+    // Links to persisted data are removed without following them, so the data stays
+    tokio::fs::remove_dir_all(app.path())
+        .await
+        .with_context(|| format!("Failed to remove {}", app.path().display()))?;
+
+    let persist_dir = PERSIST_DIR.join(&app.name);
+    if opts.purge && persist_dir.exists() {
+        tokio::fs::remove_dir_all(&persist_dir)
+            .await
+            .with_context(|| format!("Failed to remove {}", persist_dir.display()))?;
+        println!("Removed persisted data of {}", app.name);
+    }
+
+    println!("Uninstalled {}", app.name);
+
+    Ok(())
+}
+
+async fn run_uninstaller(
+    app: &InstalledApp,
+    version: &AppVersion<'_>,
+    uninstaller: &Uninstaller,
+) -> anyhow::Result<()> {
+    if let Some(script) = &uninstaller.script {
+        run_script(script, &app.name, &version.version).await?;
+    }
+
+    if let Some(file) = &uninstaller.file {
+        let dir = version.path();
+        let args = uninstaller
+            .args
+            .iter()
+            .flatten()
+            .map(|a| a.replace("$dir", &dir.to_string_lossy()))
+            .collect::<Vec<_>>();
+        let status = tokio::process::Command::new(dir.join(file))
+            .args(args)
+            .current_dir(&dir)
+            .status()
+            .await
+            .with_context(|| format!("Failed to start {}", file))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", file, status);
+        }
+    }
+
     Ok(())
 }
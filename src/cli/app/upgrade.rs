@@ -1,15 +1,209 @@
+use anyhow::Context as _;
 use clap::Args;
+use interface::{
+    bucket::get_buckets,
+    bucket_app::{BucketApp, BucketAppName, BucketsAppsRepository},
+    installed_app::{installed_apps, InstalledApp},
+    manifest::Manifest,
+    version::Version,
+};
+use tabled::settings::Style;
 
 use crate::cli::CliResult;
 
+use super::install::{
+    download::{self, DownloadResult},
+    install_app,
+};
+
 #[derive(Debug, Args)]
 pub struct UpgradeArgs {
     /// The app to upgrade
     /// If no package is specified, all packages are upgraded
     name: Option<Vec<String>>,
+    /// Number of times to retry a download that failed with a network or server error
+    #[clap(long, default_value_t = 3)]
+    retries: u32,
+}
+
+enum UpgradeResult {
+    Upgraded {
+        from: String,
+        to: String,
+    },
+    Skipped {
+        version: String,
+        reason: String,
+    },
+    Failed {
+        from: String,
+        to: String,
+        error: String,
+    },
 }
 
 pub async fn start(opts: UpgradeArgs) -> CliResult {
-    println!("upgrade");
+    start_inner(opts).await.map_err(|e| format!("{:#}", e))
+}
+
+async fn start_inner(opts: UpgradeArgs) -> anyhow::Result<()> {
+    let buckets = get_buckets().await.context("Failed to get buckets")?;
+    let apps = BucketsAppsRepository::from_buckets(&buckets)
+        .await
+        .context("Failed to get apps from buckets")?;
+
+    let installed = match opts.name {
+        Some(names) if !names.is_empty() => {
+            let mut installed = Vec::<InstalledApp>::new();
+            for name in names {
+                if installed.iter().any(|app| app.name == name) {
+                    continue;
+                }
+                let app = InstalledApp::from_name(&name);
+                if !app.is_installed().await {
+                    anyhow::bail!("`{}` is not installed", name);
+                }
+                installed.push(app);
+            }
+            installed
+        }
+        _ => installed_apps()
+            .await
+            .context("Failed to get installed apps")?,
+    };
+
+    let mut results = Vec::new();
+    let mut outdated = Vec::new();
+    for app in &installed {
+        match check_outdated(app, &apps).await {
+            Ok(Some((bucket_app, manifest, current))) => {
+                outdated.push((bucket_app, manifest, current))
+            }
+            Ok(None) => {}
+            Err((version, reason)) => {
+                results.push((app.name.clone(), UpgradeResult::Skipped { version, reason }))
+            }
+        }
+    }
+
+    let to_download = outdated
+        .iter()
+        .map(|(app, manifest, _)| (*app, manifest.clone()))
+        .collect::<Vec<_>>();
+    let options = download::DownloadOptions {
+        hash_check: true,
+        retry: download::RetryPolicy::new(opts.retries),
+        offline: false,
+    };
+    let downloads = download::download(&to_download, options).await?;
+
+    for (app, manifest, current) in outdated {
+        // Apps with failed downloads are not installed, other apps are still upgraded
+        let failed_download = downloads
+            .iter()
+            .find(|d| d.app == app.name && d.result.is_err());
+        if let Some(DownloadResult {
+            url,
+            result: Err(e),
+            ..
+        }) = failed_download
+        {
+            results.push((
+                app.name.clone(),
+                UpgradeResult::Failed {
+                    from: current,
+                    to: manifest.version.clone(),
+                    error: format!("Failed to download {}: {:#}", url, e),
+                },
+            ));
+            continue;
+        }
+
+        let result = match install_app(app, &manifest).await {
+            Ok(()) => UpgradeResult::Upgraded {
+                from: current,
+                to: manifest.version.clone(),
+            },
+            // `install_app` removed the half-installed version, `current` still points to the old one
+            Err(e) => UpgradeResult::Failed {
+                from: current,
+                to: manifest.version.clone(),
+                error: format!("{:#}", e),
+            },
+        };
+        results.push((app.name.clone(), result));
+    }
+
+    if results.is_empty() {
+        println!("All apps are up to date");
+        return Ok(());
+    }
+
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Name", "Version", "Result"]);
+    let mut failed = 0;
+    for (name, result) in results {
+        let (version, result) = match result {
+            UpgradeResult::Upgraded { from, to } => {
+                (format!("{} -> {}", from, to), "Upgraded".to_string())
+            }
+            UpgradeResult::Skipped { version, reason } => (version, format!("Skipped: {}", reason)),
+            UpgradeResult::Failed { from, to, error } => {
+                failed += 1;
+                (format!("{} -> {}", from, to), format!("Failed: {}", error))
+            }
+        };
+        builder.push_record([name, version, result]);
+    }
+
+    let table = builder.build().with(Style::rounded()).to_string();
+    println!("{}", table);
+
+    if failed > 0 {
+        anyhow::bail!("Failed to upgrade {} apps", failed);
+    }
+
     Ok(())
 }
+
+/// Find the manifest of the installed app in its source bucket.
+/// Returns `None` if the app is up to date, or the version and the reason if it can't be upgraded.
+async fn check_outdated<'a>(
+    app: &InstalledApp,
+    apps: &'a BucketsAppsRepository<'a>,
+) -> Result<Option<(&'a BucketApp<'a>, Manifest, String)>, (String, String)> {
+    let current = app
+        .current_version()
+        .await
+        .map_err(|_| ("-".to_string(), "No current version".to_string()))?;
+    let version = current.version.clone();
+    let info = current
+        .install_info()
+        .await
+        .map_err(|_| (version.clone(), "No install info".to_string()))?;
+
+    let bucket_app = BucketAppName {
+        bucket_name: Some(info.bucket.name.clone()),
+        name: app.name.clone(),
+    }
+    .get_bucket_app(apps)
+    .ok_or_else(|| {
+        (
+            version.clone(),
+            format!("Not found in bucket `{}`", info.bucket.name),
+        )
+    })?;
+    let manifest = bucket_app
+        .manifest()
+        .await
+        .map_err(|_| (version.clone(), "Failed to parse manifest".to_string()))?;
+
+    match Version::from(manifest.version.as_str()).cmp(&Version::from(version.as_str())) {
+        std::cmp::Ordering::Equal => Ok(None),
+        std::cmp::Ordering::Less => Err((
+            version,
+            format!("Installed version is newer than {}", manifest.version),
+        )),
+        std::cmp::Ordering::Greater => Ok(Some((bucket_app, manifest, version))),
+    }
+}
@@ -0,0 +1,75 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use interface::bucket::{known_bucket_url, validate_bucket_name, Bucket};
+
+use crate::cli::CliResult;
+
+pub async fn start(name: String, url: Option<String>) -> CliResult {
+    validate_bucket_name(&name).map_err(|e| e.to_string())?;
+    let url = match url {
+        Some(url) => url,
+        None => known_bucket_url(&name)
+            .ok_or_else(|| {
+                format!(
+                    "`{}` is not a known bucket. Please specify the url of the bucket.",
+                    name
+                )
+            })?
+            .to_string(),
+    };
+
+    println!("Adding bucket {} ({})", name, url);
+
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} objects",
+        )
+        .unwrap()
+        .progress_chars("#>-"),
+    );
+    pb.set_message(format!("Cloning {}", name));
+
+    let config = crate::cli::load_config()
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+    let bucket = {
+        let pb = pb.clone();
+        let name = name.clone();
+        tokio::task::spawn_blocking(move || {
+            Bucket::new_with_clone(&name, &url, &config.network, |progress| {
+                pb.set_length(progress.total_objects() as u64);
+                pb.set_position(progress.received_objects() as u64);
+                true
+            })
+        })
+        .await
+        .map_err(|e| format!("Failed to clone bucket: {}", e))?
+        .map_err(|e| format!("Failed to clone bucket: {}", e))?
+    };
+    pb.finish_with_message(format!("Cloned {}", name));
+
+    let check = match bucket.check_manifests().await {
+        Ok(check) if check.parsed > 0 => check,
+        res => {
+            tokio::fs::remove_dir_all(bucket.path())
+                .await
+                .map_err(|e| format!("Failed to remove invalid bucket: {}", e))?;
+            return Err(match res {
+                Ok(_) => format!("`{}` does not contain any valid manifest", name),
+                Err(e) => format!("Failed to read manifests of `{}`: {}", name, e),
+            });
+        }
+    };
+    if !check.failed.is_empty() {
+        println!(
+            "{}{} manifests could not be parsed: {}",
+            console::style("Warning: ").yellow(),
+            check.failed.len(),
+            check.failed.join(", ")
+        );
+    }
+
+    println!("Added bucket {} with {} apps", name, check.parsed);
+
+    Ok(())
+}
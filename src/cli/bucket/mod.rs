@@ -2,7 +2,9 @@ use clap::{Args, Subcommand};
 
 use super::CliResult;
 
+mod add;
 mod list;
+mod remove;
 pub mod update;
 
 #[derive(Debug, Args)]
@@ -13,20 +15,26 @@ pub struct BucketArgs {
 
 #[derive(Subcommand, Debug)]
 pub enum BucketCommand {
-    Add { name: String, url: String },
-    Remove { name: String },
+    /// Add a bucket. `url` can be omitted for known buckets.
+    Add {
+        name: String,
+        url: Option<String>,
+    },
+    /// Remove a bucket
+    Remove {
+        name: String,
+        /// Remove the bucket even if there are apps installed from it
+        #[clap(long, default_value_t = false)]
+        force: bool,
+    },
     Update(update::UpdateArgs),
     List {},
 }
 
 pub async fn start(opts: BucketArgs) -> CliResult {
     match opts.command {
-        BucketCommand::Add { name, url } => {
-            todo!()
-        }
-        BucketCommand::Remove { name } => {
-            todo!()
-        }
+        BucketCommand::Add { name, url } => add::start(name, url).await,
+        BucketCommand::Remove { name, force } => remove::start(name, force).await,
         BucketCommand::Update(args) => update::start(args).await,
         BucketCommand::List {} => list::start().await,
     }
@@ -0,0 +1,43 @@
+use interface::bucket::get_buckets;
+
+use crate::cli::CliResult;
+
+pub async fn start(name: String, force: bool) -> CliResult {
+    let buckets = get_buckets()
+        .await
+        .map_err(|e| format!("Failed to get buckets: {}", e))?;
+    let bucket = buckets
+        .into_iter()
+        .find(|b| b.name == name)
+        .ok_or_else(|| format!("Bucket `{}` not found", name))?;
+
+    let apps = bucket
+        .installed_apps()
+        .await
+        .map_err(|e| format!("Failed to get installed apps: {}", e))?;
+    if !apps.is_empty() {
+        let names = apps.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+        if !force {
+            return Err(format!(
+                "These apps are installed from `{}`: {}\nThey can't be upgraded after the bucket is removed. Use `--force` to remove it anyway.",
+                name,
+                names.join(", ")
+            ));
+        }
+        println!(
+            "{}These apps are installed from `{}` and won't be upgraded anymore: {}",
+            console::style("Warning: ").yellow(),
+            name,
+            names.join(", ")
+        );
+    }
+
+    bucket
+        .remove()
+        .await
+        .map_err(|e| format!("Failed to remove bucket: {}", e))?;
+
+    println!("Removed bucket {}", name);
+
+    Ok(())
+}
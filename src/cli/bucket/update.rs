@@ -1,11 +1,129 @@
 use clap::Args;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use interface::bucket::{get_buckets, Bucket, BucketUpdate, ManifestChangeKind};
+use tabled::settings::Style;
 
 use crate::cli::CliResult;
 
 #[derive(Debug, Args)]
-pub struct UpdateArgs {}
+pub struct UpdateArgs {
+    /// Buckets to update
+    /// If no bucket is specified, all buckets are updated
+    names: Vec<String>,
+}
 
 pub async fn start(opts: UpdateArgs) -> CliResult {
-    println!("update");
+    let buckets = get_buckets()
+        .await
+        .map_err(|e| format!("Failed to get buckets: {}", e))?;
+
+    for name in &opts.names {
+        if !buckets.iter().any(|b| &b.name == name) {
+            return Err(format!("Bucket `{}` not found", name));
+        }
+    }
+    let buckets = buckets
+        .into_iter()
+        .filter(|b| opts.names.is_empty() || opts.names.contains(&b.name))
+        .collect::<Vec<_>>();
+
+    let config = crate::cli::load_config()
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+
+    let m = MultiProgress::new();
+    let pb_style =
+        ProgressStyle::with_template("{spinner:.green} {prefix:.bold} {wide_msg}").unwrap();
+
+    let mut handles = Vec::new();
+    for bucket in buckets {
+        let pb = m.add(ProgressBar::new_spinner());
+        pb.set_style(pb_style.clone());
+        pb.set_prefix(bucket.name.clone());
+        pb.set_message("Fetching...");
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        let network = config.network.clone();
+        handles.push((
+            bucket.clone(),
+            tokio::task::spawn_blocking(move || {
+                let res = bucket.update(&network, |progress| {
+                    pb.set_message(format!(
+                        "Fetching... {}/{} objects",
+                        progress.received_objects(),
+                        progress.total_objects()
+                    ));
+                    true
+                });
+                match &res {
+                    Ok(BucketUpdate::UpToDate) => {
+                        pb.finish_with_message("Already up to date".to_string())
+                    }
+                    Ok(BucketUpdate::Updated { old, new }) => pb.finish_with_message(format!(
+                        "Updated {:.7}..{:.7}",
+                        old.to_string(),
+                        new.to_string()
+                    )),
+                    Err(e) => pb.finish_with_message(format!(
+                        "{}",
+                        console::style(format!("Failed: {}", e).replace("\n\t", " ")).red()
+                    )),
+                }
+                res
+            }),
+        ));
+    }
+
+    let mut failed = Vec::new();
+    let mut updated = Vec::new();
+    for (bucket, handle) in handles {
+        match handle.await {
+            Ok(Ok(BucketUpdate::Updated { old, new })) => updated.push((bucket, old, new)),
+            Ok(Ok(BucketUpdate::UpToDate)) => {}
+            _ => failed.push(bucket.name),
+        }
+    }
+
+    for (bucket, old, new) in updated {
+        print_changes(&bucket, old, new).await;
+    }
+
+    if !failed.is_empty() {
+        return Err(format!("Failed to update buckets: {}", failed.join(", ")));
+    }
+
     Ok(())
 }
+
+async fn print_changes(bucket: &Bucket, old: git2::Oid, new: git2::Oid) {
+    let changes = match bucket.changes(old, new).await {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("Failed to get changes of {}: {}", bucket.name, e);
+            return;
+        }
+    };
+    if changes.is_empty() {
+        return;
+    }
+
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Name", "Change", "Installed"]);
+    for change in changes {
+        let version = |v: Option<String>| v.unwrap_or_else(|| "?".to_string());
+        let description = match change.kind {
+            ManifestChangeKind::Added { version: v } => format!("Added ({})", version(v)),
+            ManifestChangeKind::Removed { version: v } => format!("Removed ({})", version(v)),
+            ManifestChangeKind::VersionChanged { old, new } => {
+                format!("{} -> {}", version(old), version(new))
+            }
+        };
+        let installed = if change.installed { "*" } else { "" };
+        builder.push_record([change.name.as_str(), &description, installed]);
+    }
+
+    let table = builder.build().with(Style::rounded()).to_string();
+
+    println!("Changes in {}", bucket.name);
+    println!("{}", table);
+}
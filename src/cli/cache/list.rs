@@ -0,0 +1,33 @@
+use std::time::SystemTime;
+
+use indicatif::HumanBytes;
+use interface::cache::{format_age, total_size};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::cli::CliResult;
+
+pub async fn start(apps: Vec<String>) -> CliResult {
+    let files = super::cached_files(&apps).await?;
+    let now = SystemTime::now();
+
+    let mut builder = Builder::default();
+    builder.push_record(["App", "Version", "Size", "Age"]);
+    for file in &files {
+        let version = if file.partial {
+            format!("{} (incomplete)", file.version)
+        } else {
+            file.version.clone()
+        };
+        builder.push_record([
+            file.app.clone(),
+            version,
+            HumanBytes(file.size).to_string(),
+            format_age(file.age(now)),
+        ]);
+    }
+
+    let table = builder.build().with(Style::rounded()).to_string();
+    println!("{table}");
+    println!("{} files, {}", files.len(), HumanBytes(total_size(&files)));
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use clap::{ArgGroup, Args, Subcommand};
+use interface::{
+    bucket::get_buckets,
+    bucket_app::BucketsAppsRepository,
+    cache::{version_of, CachedFile},
+    installed_app::{installed_apps, InstalledApp},
+};
+
+use super::CliResult;
+
+mod list;
+mod prune;
+mod rm;
+mod verify;
+
+#[derive(Debug, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached downloads. All apps are listed if none are given.
+    List { apps: Vec<String> },
+    /// Remove cached downloads of apps. `*` removes all of them.
+    #[command(visible_alias("remove"))]
+    Rm {
+        #[clap(required = true)]
+        apps: Vec<String>,
+    },
+    /// Hash cached downloads again and compare them with the manifests in the buckets
+    Verify { apps: Vec<String> },
+    /// Remove the oldest cached downloads. Downloads of installed versions are kept.
+    #[command(group(ArgGroup::new("limit").required(true).multiple(true)))]
+    Prune {
+        /// Remove downloads until the cache is at most this size, e.g. `2GB`
+        #[clap(long, group = "limit", value_parser = parse_size)]
+        max_size: Option<u64>,
+        /// Remove downloads older than this, e.g. `30d` or `2w`
+        #[clap(long, group = "limit", value_parser = parse_age)]
+        older_than: Option<std::time::Duration>,
+    },
+}
+
+fn parse_size(s: &str) -> Result<u64, String> {
+    interface::cache::parse_size(s).ok_or_else(|| format!("Invalid size: {}", s))
+}
+
+fn parse_age(s: &str) -> Result<std::time::Duration, String> {
+    interface::cache::parse_age(s).ok_or_else(|| format!("Invalid age: {}", s))
+}
+
+pub async fn start(opts: CacheArgs) -> CliResult {
+    match opts.command {
+        CacheCommand::List { apps } => list::start(apps).await,
+        CacheCommand::Rm { apps } => rm::start(apps).await,
+        CacheCommand::Verify { apps } => verify::start(apps).await,
+        CacheCommand::Prune {
+            max_size,
+            older_than,
+        } => prune::start(max_size, older_than).await,
+    }
+}
+
+/// Cached files of `apps`, or all of them if `apps` is empty. The files of an app are the ones
+/// named after it, see [`version_of`]. The versions of the apps that are installed or in a bucket
+/// tell them apart from the files of other apps with names starting with theirs.
+async fn cached_files(apps: &[String]) -> Result<Vec<CachedFile>, String> {
+    let files = interface::cache::cached_files()
+        .await
+        .map_err(|e| format!("Failed to read the cache: {}", e))?;
+    if apps.is_empty() {
+        return Ok(files);
+    }
+
+    let buckets = get_buckets()
+        .await
+        .map_err(|e| format!("Failed to get buckets: {}", e))?;
+    let repo = BucketsAppsRepository::from_buckets(&buckets)
+        .await
+        .map_err(|e| format!("Failed to get apps from buckets: {}", e))?;
+    let mut other_apps = installed_apps()
+        .await
+        .map_err(|e| format!("Failed to get apps: {}", e))?
+        .into_iter()
+        .map(|app| app.name)
+        .chain(repo.inner.values().flatten().map(|app| app.name.clone()))
+        .collect::<Vec<_>>();
+    other_apps.sort();
+    other_apps.dedup();
+    let mut versions = Vec::new();
+    for app in apps {
+        versions.push((app, known_versions(app, &repo).await));
+    }
+
+    Ok(files
+        .into_iter()
+        .filter_map(|mut file| {
+            let name = file.path.file_name()?.to_str()?;
+            let (app, version) = versions.iter().find_map(|(app, versions)| {
+                Some((app, version_of(name, app, versions, &other_apps)?))
+            })?;
+            file.app = app.to_string();
+            file.version = version;
+            Some(file)
+        })
+        .collect())
+}
+
+/// Installed versions of `app` and its versions in the buckets
+async fn known_versions(app: &str, repo: &BucketsAppsRepository<'_>) -> Vec<String> {
+    let mut versions = Vec::new();
+    let installed = InstalledApp::from_name(app);
+    if let Ok(installed) = installed.versions().await {
+        versions.extend(installed.into_iter().map(|v| v.version));
+    }
+    for bucket_app in repo.inner.values().flatten().filter(|a| a.name == app) {
+        if let Ok(manifest) = bucket_app.manifest().await {
+            versions.push(manifest.version);
+        }
+    }
+    versions
+}
@@ -0,0 +1,81 @@
+use std::time::SystemTime;
+
+use indicatif::HumanBytes;
+use interface::{
+    cache::{files_to_prune, is_cache_of, CachedFile, PART_SUFFIX},
+    installed_app::installed_apps,
+    manifest::DownloadUrl,
+};
+
+use crate::cli::CliResult;
+
+/// Downloads of an installed version, which are kept
+struct Installed {
+    app: String,
+    version: String,
+    /// Urls in the saved manifest, or `None` if it can't be read. Then all files named after the
+    /// version are kept.
+    urls: Option<Vec<DownloadUrl>>,
+}
+
+impl Installed {
+    fn is_cache(&self, file: &CachedFile) -> bool {
+        let Some(name) = file.path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        let name = name.strip_suffix(PART_SUFFIX).unwrap_or(name);
+        match &self.urls {
+            Some(urls) => urls
+                .iter()
+                .any(|url| is_cache_of(name, &self.app, &self.version, url)),
+            None => name.starts_with(&format!("{}-{}-", self.app, self.version)),
+        }
+    }
+}
+
+pub async fn start(max_size: Option<u64>, older_than: Option<std::time::Duration>) -> CliResult {
+    let files = super::cached_files(&[]).await?;
+
+    let mut installed = Vec::new();
+    let apps = installed_apps()
+        .await
+        .map_err(|e| format!("Failed to get apps: {}", e))?;
+    for app in &apps {
+        let versions = app
+            .versions()
+            .await
+            .map_err(|e| format!("Failed to get versions of {}: {}", app.name, e))?;
+        for version in versions {
+            let urls = version
+                .manifest()
+                .await
+                .ok()
+                .map(|manifest| manifest.architecture_current().url.unwrap_or_default());
+            installed.push(Installed {
+                app: app.name.clone(),
+                version: version.version,
+                urls,
+            });
+        }
+    }
+    let is_installed = |file: &CachedFile| installed.iter().any(|i| i.is_cache(file));
+
+    let prune = files_to_prune(
+        &files,
+        is_installed,
+        max_size,
+        older_than,
+        SystemTime::now(),
+    );
+    let mut freed = 0;
+    for file in &prune {
+        tokio::fs::remove_file(&file.path)
+            .await
+            .map_err(|e| format!("Failed to remove {}: {}", file.path.display(), e))?;
+        println!("Removed {}", file.path.display());
+        freed += file.size;
+    }
+
+    println!("Removed {} files, {}", prune.len(), HumanBytes(freed));
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use indicatif::HumanBytes;
+use interface::cache::total_size;
+
+use crate::cli::CliResult;
+
+pub async fn start(apps: Vec<String>) -> CliResult {
+    let apps = if apps.iter().any(|a| a == "*") {
+        Vec::new()
+    } else {
+        apps
+    };
+    let files = super::cached_files(&apps).await?;
+
+    for file in &files {
+        tokio::fs::remove_file(&file.path)
+            .await
+            .map_err(|e| format!("Failed to remove {}: {}", file.path.display(), e))?;
+    }
+
+    println!(
+        "Removed {} files, {}",
+        files.len(),
+        HumanBytes(total_size(&files))
+    );
+    Ok(())
+}
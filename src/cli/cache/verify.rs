@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use interface::{
+    bucket::get_buckets,
+    bucket_app::{BucketAppName, BucketsAppsRepository},
+    cache::{cache_path, CachedFile},
+    manifest::ManifestHash,
+};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::cli::{app::install::download, CliResult};
+
+pub async fn start(apps: Vec<String>) -> CliResult {
+    start_inner(apps).await.map_err(|e| format!("{:#}", e))
+}
+
+async fn start_inner(apps: Vec<String>) -> anyhow::Result<()> {
+    let files = super::cached_files(&apps)
+        .await
+        .map_err(anyhow::Error::msg)?;
+    let buckets = get_buckets().await.context("Failed to get buckets")?;
+    let repo = BucketsAppsRepository::from_buckets(&buckets)
+        .await
+        .context("Failed to get apps from buckets")?;
+
+    // Expected hashes by cache file name, for the versions in the buckets
+    let mut hashes = HashMap::new();
+    let mut app_names = files.iter().map(|f| f.app.as_str()).collect::<Vec<_>>();
+    app_names.dedup();
+    for name in app_names {
+        let app_name = BucketAppName {
+            bucket_name: None,
+            name: name.to_string(),
+        };
+        let Some(app) = app_name.get_bucket_app(&repo) else {
+            continue;
+        };
+        let manifest = app
+            .manifest()
+            .await
+            .with_context(|| format!("Failed to get manifest of {}", name))?;
+        let url_hashes = manifest
+            .architecture_current()
+            .url_hashes()
+            .with_context(|| format!("Invalid manifest of {}", name))?;
+        for (url, hash) in url_hashes {
+            let path = cache_path(name, &manifest.version, &url);
+            hashes.insert(path, hash);
+        }
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["App", "Version", "Result"]);
+    let mut mismatches = 0;
+    for file in &files {
+        let result = verify(file, hashes.get(&file.path)).await;
+        if result == "Mismatch" {
+            mismatches += 1;
+        }
+        builder.push_record([file.app.as_str(), &file.version, &result]);
+    }
+
+    let table = builder.build().with(Style::rounded()).to_string();
+    println!("{table}");
+
+    if mismatches > 0 {
+        anyhow::bail!("{} cached files don't match their manifests", mismatches);
+    }
+    Ok(())
+}
+
+async fn verify(file: &CachedFile, expected: Option<&Option<ManifestHash>>) -> String {
+    if file.partial {
+        return "Skipped (incomplete)".to_string();
+    }
+    let expected = match expected {
+        None => return "Skipped (not in the bucket)".to_string(),
+        Some(None) => return "Skipped (no hash)".to_string(),
+        Some(Some(expected)) => expected,
+    };
+    match download::hash_file(&file.path, expected.algorithm).await {
+        Ok(actual) if actual == *expected => "OK".to_string(),
+        Ok(_) => "Mismatch".to_string(),
+        Err(e) => format!("Failed to read: {}", e),
+    }
+}
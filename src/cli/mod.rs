@@ -1,9 +1,16 @@
-use std::time::Instant;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
 
+use anyhow::Context as _;
 use clap::{Parser, Subcommand};
+use interface::config::Config;
 
 mod app;
 mod bucket;
+mod cache;
+mod shim;
 
 type CliResult = Result<(), String>;
 
@@ -11,6 +18,20 @@ type CliResult = Result<(), String>;
 struct Cli {
     #[clap(subcommand)]
     command: Command,
+
+    /// Don't verify TLS certificates of downloads and buckets. Only use this for debugging.
+    #[clap(long, global = true, default_value_t = false)]
+    insecure: bool,
+}
+
+/// Set by `--insecure` and applied to the config in [`load_config`]
+static INSECURE: AtomicBool = AtomicBool::new(false);
+
+/// Load the config, with the options given on the command line applied
+pub(crate) async fn load_config() -> anyhow::Result<Config> {
+    let mut config = Config::load().await.context("Failed to load config")?;
+    config.network.insecure = INSECURE.load(Ordering::Relaxed);
+    Ok(config)
 }
 
 #[derive(Subcommand, Debug)]
@@ -31,6 +52,9 @@ enum Command {
     #[command(visible_alias("u"))]
     Update(bucket::update::UpdateArgs),
 
+    /// Switch the current version of an app. This is alias of `app reset`
+    Reset(app::reset::ResetArgs),
+
     /// Search apps. This is alias of `app search`
     Search(app::search::SearchArgs),
 
@@ -39,11 +63,30 @@ enum Command {
 
     /// Manage buckets
     Bucket(bucket::BucketArgs),
+
+    /// Manage the download cache
+    Cache(cache::CacheArgs),
+
+    /// Manage shims
+    Shim(shim::ShimArgs),
 }
 
 pub async fn start() {
     let cli = Cli::parse();
 
+    if cli.insecure {
+        eprintln!(
+            "{}",
+            console::style(
+                "WARNING: --insecure is set. TLS certificates are NOT verified, so anyone on the \
+                 network can tamper with downloads and buckets."
+            )
+            .red()
+            .bold()
+        );
+        INSECURE.store(true, Ordering::Relaxed);
+    }
+
     let start = Instant::now();
 
     let res: CliResult = match cli.command {
@@ -51,9 +94,12 @@ pub async fn start() {
         Command::Uninstall(args) => app::uninstall::start(args).await,
         Command::Upgrade(args) => app::upgrade::start(args).await,
         Command::Update(args) => bucket::update::start(args).await,
+        Command::Reset(args) => app::reset::start(args).await,
         Command::Search(args) => app::search::start(args).await,
         Command::App(args) => app::start(args).await,
         Command::Bucket(args) => bucket::start(args).await,
+        Command::Cache(args) => cache::start(args).await,
+        Command::Shim(args) => shim::start(args).await,
     };
 
     if let Err(msg) = res {
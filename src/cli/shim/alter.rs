@@ -0,0 +1,60 @@
+use anyhow::Context as _;
+use interface::shim::{providers, Shim};
+
+use crate::cli::{app::install::shim::create_shim, CliResult};
+
+pub async fn start(name: String, app: Option<String>) -> CliResult {
+    start_inner(name, app).await.map_err(|e| format!("{:#}", e))
+}
+
+async fn start_inner(name: String, app: Option<String>) -> anyhow::Result<()> {
+    let owner = Shim::read(&name)
+        .await
+        .context("Failed to read shim")?
+        .and_then(|s| s.owner());
+    let providers = providers()
+        .await
+        .context("Failed to read installed apps")?
+        .remove(&name)
+        .unwrap_or_default();
+
+    let provider = match app {
+        Some(app) => providers
+            .iter()
+            .find(|p| p.app == app)
+            .with_context(|| format!("{} doesn't provide shim `{}`", app, name))?,
+        None => {
+            let others = providers
+                .iter()
+                .filter(|p| Some(&p.app) != owner.as_ref())
+                .collect::<Vec<_>>();
+            match others.as_slice() {
+                [] => anyhow::bail!("No other installed app provides shim `{}`", name),
+                [provider] => *provider,
+                _ => anyhow::bail!(
+                    "Shim `{}` is provided by {}. Choose one with `shim alter {} <app>`",
+                    name,
+                    others
+                        .iter()
+                        .map(|p| p.app.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    name
+                ),
+            }
+        }
+    };
+
+    if owner.as_ref() == Some(&provider.app) {
+        println!("Shim `{}` already runs {}", name, provider.app);
+        return Ok(());
+    }
+    let shim = create_shim(&provider.app, &provider.version, &provider.bin).await?;
+    println!(
+        "Shim `{}` now runs {} of {}",
+        name,
+        shim.path.display(),
+        provider.app
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use interface::shim::{providers, Shim};
+
+use crate::cli::CliResult;
+
+pub async fn start(name: String) -> CliResult {
+    let shim = Shim::read(&name)
+        .await
+        .map_err(|e| format!("Failed to read shim `{}`: {}", name, e))?
+        .ok_or_else(|| format!("Shim `{}` not found", name))?;
+    let owner = shim.owner();
+    let providers = providers()
+        .await
+        .map_err(|e| format!("Failed to read installed apps: {}", e))?
+        .remove(&name)
+        .unwrap_or_default();
+
+    println!("Name:   {}", shim.name);
+    println!("App:    {}", owner.as_deref().unwrap_or("-"));
+    if shim.path.exists() {
+        println!("Target: {}", shim.path.display());
+    } else {
+        println!("Target: {} (missing)", shim.path.display());
+    }
+    println!("Args:   {}", shim.args.as_deref().unwrap_or("-"));
+
+    let others = providers
+        .iter()
+        .map(|p| p.app.as_str())
+        .filter(|app| Some(*app) != owner.as_deref())
+        .collect::<Vec<_>>();
+    if !others.is_empty() {
+        println!(
+            "Also provided by {}. Switch with `shim alter {} <app>`",
+            others.join(", "),
+            name
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use interface::shim::shims;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::cli::CliResult;
+
+pub async fn start() -> CliResult {
+    let shims = shims()
+        .await
+        .map_err(|e| format!("Failed to read shims: {}", e))?;
+
+    let mut builder = Builder::default();
+    builder.push_record(["Name", "App", "Target"]);
+    for shim in &shims {
+        let target = if shim.path.exists() {
+            shim.path.display().to_string()
+        } else {
+            format!("{} (missing)", shim.path.display())
+        };
+        builder.push_record([
+            shim.name.clone(),
+            shim.owner().unwrap_or_else(|| "-".to_string()),
+            target,
+        ]);
+    }
+
+    let table = builder.build().with(Style::rounded()).to_string();
+    println!("{table}");
+    println!("{} shims", shims.len());
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use clap::{Args, Subcommand};
+
+use super::CliResult;
+
+mod alter;
+mod info;
+mod list;
+mod repair;
+mod rm;
+
+#[derive(Debug, Args)]
+pub struct ShimArgs {
+    #[command(subcommand)]
+    command: ShimCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ShimCommand {
+    /// List shims and the apps that own them
+    List,
+    /// Show the app, target and arguments of a shim
+    Info { name: String },
+    /// Point a shim to another installed app that provides it
+    Alter {
+        name: String,
+        /// App to switch to. Required if more than one other app provides the shim.
+        app: Option<String>,
+    },
+    /// Create shims of installed apps again, and remove shims whose target is gone
+    Repair,
+    /// Remove shims
+    #[command(visible_alias("remove"))]
+    Rm {
+        #[clap(required = true)]
+        names: Vec<String>,
+    },
+}
+
+pub async fn start(opts: ShimArgs) -> CliResult {
+    match opts.command {
+        ShimCommand::List => list::start().await,
+        ShimCommand::Info { name } => info::start(name).await,
+        ShimCommand::Alter { name, app } => alter::start(name, app).await,
+        ShimCommand::Repair => repair::start().await,
+        ShimCommand::Rm { names } => rm::start(names).await,
+    }
+}
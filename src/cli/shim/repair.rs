@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use interface::{
+    dir::INSTALL_DIR,
+    shim::{providers, repairs, shims, Repair},
+};
+
+use crate::cli::{
+    app::install::shim::{create_shim, remove_shim},
+    CliResult,
+};
+
+pub async fn start() -> CliResult {
+    start_inner().await.map_err(|e| format!("{:#}", e))
+}
+
+async fn start_inner() -> anyhow::Result<()> {
+    let shims = shims().await.context("Failed to read shims")?;
+    let providers = providers().await.context("Failed to read installed apps")?;
+
+    let (mut created, mut removed) = (0, 0);
+    let mut failed = Vec::new();
+    for repair in repairs(&shims, &providers, &INSTALL_DIR.join("apps"), Path::exists) {
+        match repair {
+            Repair::Create(provider) => {
+                match create_shim(&provider.app, &provider.version, &provider.bin).await {
+                    Ok(_) => created += 1,
+                    Err(e) => {
+                        eprintln!("Failed to create shim `{}`: {:#}", provider.bin.name, e);
+                        failed.push(provider.bin.name);
+                    }
+                }
+            }
+            Repair::Remove(name) => match remove_shim(&name).await {
+                Ok(_) => {
+                    println!("Removed shim `{}`, whose target is gone", name);
+                    removed += 1;
+                }
+                Err(e) => {
+                    eprintln!("Failed to remove shim `{}`: {:#}", name, e);
+                    failed.push(name);
+                }
+            },
+        }
+    }
+
+    println!(
+        "Created {} shims, removed {}, failed {}",
+        created,
+        removed,
+        failed.len()
+    );
+    if !failed.is_empty() {
+        anyhow::bail!("Failed to repair shims: {}", failed.join(", "));
+    }
+    Ok(())
+}
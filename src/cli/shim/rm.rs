@@ -0,0 +1,12 @@
+use crate::cli::{app::install::shim::remove_shim, CliResult};
+
+pub async fn start(names: Vec<String>) -> CliResult {
+    for name in &names {
+        let removed = remove_shim(name).await.map_err(|e| format!("{:#}", e))?;
+        if !removed {
+            return Err(format!("Shim `{}` not found", name));
+        }
+        println!("Removed shim `{}`", name);
+    }
+    Ok(())
+}